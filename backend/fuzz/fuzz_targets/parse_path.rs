@@ -0,0 +1,77 @@
+//! Look for panics while parsing and writing back names with any configuration:
+//!
+//! ```sh
+//! cargo +nightly fuzz run parse_path
+//! ```
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use backend::{
+    configuration::{TimestampConfiguration, TimestampVariantConfiguration},
+    FuzzyDates, StructuredNameConfiguration, StructuredPath, TagConfiguration, TagQuotes,
+    UnicodeForm,
+};
+use camino::Utf8Path;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    /// Date, date-time, offset date-time, time, date range, week, month and quarter.
+    formats: [Vec<String>; 8],
+    fuzzy_dates: bool,
+    main_separators: Vec<String>,
+    between_separators: Vec<String>,
+    /// NFD when true, NFC when false.
+    decomposed_tags: Option<bool>,
+    /// Opening and closing quotes.
+    quotes: Option<(String, String)>,
+    path: String,
+}
+
+fuzz_target!(|input: Input| {
+    let [date, date_time, offset_date_time, time, date_range, week, month, quarter] =
+        input.formats.map(|formats| TimestampVariantConfiguration {
+            formats,
+            fuzzy: None,
+        });
+
+    let configuration = StructuredNameConfiguration {
+        timestamp_configuration: TimestampConfiguration {
+            date: TimestampVariantConfiguration {
+                fuzzy: input.fuzzy_dates.then(FuzzyDates::default),
+                ..date
+            },
+            date_time,
+            offset_date_time,
+            time,
+            date_range,
+            week,
+            month,
+            quarter,
+            default_timezone: None,
+            import: vec![],
+        },
+        tag_configuration: TagConfiguration {
+            tag_main_separators: input.main_separators,
+            tag_between_separators: input.between_separators,
+            unicode_form: input.decomposed_tags.map(|decomposed| match decomposed {
+                true => UnicodeForm::Nfd,
+                false => UnicodeForm::Nfc,
+            }),
+            quotes: input.quotes.map(|(open, close)| TagQuotes { open, close }),
+            ..TagConfiguration::default()
+        },
+    };
+
+    let path = Utf8Path::new(&input.path);
+    let mut parsed = StructuredPath::parse_from(path, &configuration);
+
+    // Names are written back as they were, paths may lose separators.
+    if path.file_name() == Some(input.path.as_str()) {
+        assert_eq!(parsed.to_string(), input.path);
+    }
+
+    let _ = parsed.normalise();
+    let _ = parsed.to_string();
+});
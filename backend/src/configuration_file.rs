@@ -0,0 +1,170 @@
+//! Configuration is layered, each layer overriding the values of the previous ones:
+//!  1. the built-in defaults,
+//!  2. the user configuration, `tagger/config.toml` in the user configuration directory
+//!     (`$XDG_CONFIG_HOME` on Linux),
+//!  3. every `.tagger.toml` found in the parent directories of the target, from the farthest to
+//!     the nearest.
+//!
+//! A configuration file only needs the values it overrides:
+//!
+//! ```toml
+//! [timestamp]
+//! date.formats = ["%Y_%m_%d", "%Y-%m-%d"]
+//! time.formats = ["%Hh%M"]
+//! default_timezone = "Europe/Paris"
+//!
+//! [timestamp.date.fuzzy]
+//! order = "day_first"
+//!
+//! [[timestamp.import]]
+//! pattern = '^Scan_(?P<timestamp>\d{8})'
+//! format = "%Y%m%d"
+//!
+//! [tags]
+//! main_separators = [" -- "]
+//! between_separators = [" "]
+//! order = "alphabetical"
+//! unicode_form = "nfc"
+//! quotes = { open = "[", close = "]" }
+//! key_value_delimiters = ["=", ":"]
+//! ```
+
+use crate::StructuredNameConfiguration;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{collections::HashMap, fs, io};
+use thiserror::Error;
+use toml::{Table, Value};
+
+/// Name of the per-directory configuration files.
+pub const DIRECTORY_CONFIGURATION_FILE_NAME: &str = ".tagger.toml";
+
+/// Everything that can go wrong while loading the configuration.
+#[derive(Debug, Error)]
+pub enum ConfigurationError {
+    #[error("cannot read the configuration file {path}")]
+    Read {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid configuration file {path}")]
+    Parse {
+        path: Utf8PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("invalid configuration once merged")]
+    Merge(#[source] toml::de::Error),
+}
+
+impl StructuredNameConfiguration {
+    /// Load the configuration applying to a file/directory, see the [module](self) documentation.
+    pub fn load_for(target: &Utf8Path) -> Result<StructuredNameConfiguration, ConfigurationError> {
+        let mut files = Vec::new();
+        files.extend(Self::user_configuration_path().filter(|path| path.is_file()));
+        files.extend(Self::directory_configuration_paths(target));
+
+        Self::load_from_files(&files)
+    }
+
+    /// Merge the files on top of the defaults, last one wins.
+    pub fn load_from_files(
+        files: &[Utf8PathBuf],
+    ) -> Result<StructuredNameConfiguration, ConfigurationError> {
+        let mut merged = Table::new();
+
+        for path in files {
+            let content = fs::read_to_string(path).map_err(|source| ConfigurationError::Read {
+                path: path.clone(),
+                source,
+            })?;
+            let table = content
+                .parse::<Table>()
+                .map_err(|source| ConfigurationError::Parse {
+                    path: path.clone(),
+                    source,
+                })?;
+
+            merge_tables(&mut merged, table);
+        }
+
+        Value::Table(merged)
+            .try_into()
+            .map_err(ConfigurationError::Merge)
+    }
+
+    /// `tagger/config.toml` in the user configuration directory, if there is such a directory.
+    pub fn user_configuration_path() -> Option<Utf8PathBuf> {
+        let directory = Utf8PathBuf::from_path_buf(dirs::config_dir()?).ok()?;
+        Some(directory.join("tagger").join("config.toml"))
+    }
+
+    /// Existing `.tagger.toml` files in the parent directories of the target, the farthest first.
+    pub fn directory_configuration_paths(target: &Utf8Path) -> Vec<Utf8PathBuf> {
+        let parent = match target.parent() {
+            Some(parent) if !parent.as_str().is_empty() => parent,
+            _ => Utf8Path::new("."),
+        };
+        // Relative paths and `..` would stop the walk too early.
+        let parent = parent
+            .canonicalize_utf8()
+            .unwrap_or_else(|_| parent.to_path_buf());
+
+        let mut paths: Vec<_> = parent
+            .ancestors()
+            .map(|directory| directory.join(DIRECTORY_CONFIGURATION_FILE_NAME))
+            .filter(|path| path.is_file())
+            .collect();
+        paths.reverse();
+        paths
+    }
+}
+
+/// Configurations of many paths, loaded once per directory.
+#[derive(Debug, Default)]
+pub struct ConfigurationCache {
+    configurations: HashMap<Utf8PathBuf, StructuredNameConfiguration>,
+}
+
+impl ConfigurationCache {
+    pub fn load_all<'a>(
+        paths: impl IntoIterator<Item = &'a Utf8PathBuf>,
+    ) -> Result<ConfigurationCache, ConfigurationError> {
+        let mut cache = ConfigurationCache::default();
+
+        for path in paths {
+            let directory = directory_of(path);
+            if !cache.configurations.contains_key(directory) {
+                let configuration = StructuredNameConfiguration::load_for(path)?;
+                cache
+                    .configurations
+                    .insert(directory.to_path_buf(), configuration);
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// The configuration of a path given to [ConfigurationCache::load_all].
+    pub fn get(&self, path: &Utf8Path) -> Option<&StructuredNameConfiguration> {
+        self.configurations.get(directory_of(path))
+    }
+}
+
+fn directory_of(path: &Utf8Path) -> &Utf8Path {
+    path.parent().unwrap_or(Utf8Path::new(""))
+}
+
+/// Tables are merged key by key, any other value is replaced.
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
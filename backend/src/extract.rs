@@ -0,0 +1,429 @@
+//! Read the timestamps embedded in the content of files: the capture date of photos and videos,
+//! the creation date of documents. Unlike the filesystem dates, they survive copies.
+//!
+//! Extractors are tried in order, see [Extractors::extract]. New ones only need to implement
+//! [TimestampExtractor].
+
+use crate::TimestampValue;
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use exif::{In, Tag, Value};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+};
+use thiserror::Error;
+
+/// Everything that can go wrong while reading the content of a file.
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("cannot read {path}")]
+    Io {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Documents are only read up to this size from their start, and from their end for PDF files,
+/// whatever their size.
+const READ_LIMIT: u64 = 1 << 20;
+
+/// Something able to find a timestamp in some kinds of files.
+pub trait TimestampExtractor {
+    /// Whether the extractor knows the files with this extension, in lower case.
+    fn handles(&self, extension: &str) -> bool;
+
+    /// `Ok(None)` when the file has no such timestamp.
+    fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError>;
+}
+
+/// Extractors tried one after the other.
+pub struct Extractors {
+    extractors: Vec<Box<dyn TimestampExtractor>>,
+}
+
+/// Every built-in extractor, the most specific first.
+impl Default for Extractors {
+    fn default() -> Self {
+        Extractors {
+            extractors: vec![
+                Box::new(ExifExtractor),
+                Box::new(QuickTimeExtractor),
+                Box::new(PdfExtractor),
+                Box::new(XmpExtractor),
+            ],
+        }
+    }
+}
+
+impl Extractors {
+    pub fn push(&mut self, extractor: Box<dyn TimestampExtractor>) {
+        self.extractors.push(extractor);
+    }
+
+    /// The timestamp of the first extractor handling the file and finding one.
+    pub fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError> {
+        let extension = path.extension().unwrap_or_default().to_lowercase();
+
+        for extractor in &self.extractors {
+            if extractor.handles(&extension) {
+                if let Some(value) = extractor.extract(path)? {
+                    return Ok(Some(value));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// `DateTimeOriginal` of JPEG, TIFF, HEIF, PNG and WebP pictures, with `OffsetTimeOriginal` when
+/// there is one.
+pub struct ExifExtractor;
+
+impl TimestampExtractor for ExifExtractor {
+    fn handles(&self, extension: &str) -> bool {
+        matches!(
+            extension,
+            "jpg" | "jpeg" | "tif" | "tiff" | "heic" | "heif" | "avif" | "png" | "webp" | "dng"
+        )
+    }
+
+    fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError> {
+        let file = File::open(path).map_err(|source| io_error(path, source))?;
+
+        // Invalid EXIF data is no timestamp, the next extractors may still find one.
+        let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+            return Ok(None);
+        };
+
+        let ascii = |tag| match exif.get_field(tag, In::PRIMARY).map(|field| &field.value) {
+            Some(Value::Ascii(values)) => values
+                .first()
+                .and_then(|value| std::str::from_utf8(value).ok())
+                .map(str::trim),
+            _ => None,
+        };
+
+        let Some(date_time) = ascii(Tag::DateTimeOriginal)
+            .and_then(|text| NaiveDateTime::parse_from_str(text, "%Y:%m:%d %H:%M:%S").ok())
+        else {
+            return Ok(None);
+        };
+
+        let offset =
+            ascii(Tag::OffsetTimeOriginal).and_then(|text| text.parse::<FixedOffset>().ok());
+
+        Ok(Some(match offset {
+            Some(offset) => match offset.from_local_datetime(&date_time).single() {
+                Some(date_time) => TimestampValue::OffsetDateTime(date_time),
+                None => TimestampValue::DateTime(date_time),
+            },
+            None => TimestampValue::DateTime(date_time),
+        }))
+    }
+}
+
+/// `creation_time` of the movie header of MP4 and QuickTime videos, always in UTC.
+pub struct QuickTimeExtractor;
+
+/// Seconds between 1904-01-01, the QuickTime epoch, and 1970-01-01.
+const QUICKTIME_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+impl TimestampExtractor for QuickTimeExtractor {
+    fn handles(&self, extension: &str) -> bool {
+        matches!(extension, "mp4" | "m4v" | "m4a" | "mov" | "3gp")
+    }
+
+    fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError> {
+        let read = || -> io::Result<Option<TimestampValue>> {
+            let mut file = BufReader::new(File::open(path)?);
+            let end = file.seek(SeekFrom::End(0))?;
+            file.seek(SeekFrom::Start(0))?;
+
+            let Some(movie_size) = find_box(&mut file, end, b"moov")? else {
+                return Ok(None);
+            };
+            let Some(movie_end) = file
+                .stream_position()?
+                .checked_add(movie_size)
+                .filter(|movie_end| *movie_end <= end)
+            else {
+                return Ok(None);
+            };
+            let Some(header_size) = find_box(&mut file, movie_end, b"mvhd")? else {
+                return Ok(None);
+            };
+
+            // Version, then flags, then the creation time on 8 bytes in version 1, 4 otherwise.
+            let mut version = [0; 4];
+            file.read_exact(&mut version)?;
+            if header_size < if version[0] == 1 { 12 } else { 8 } {
+                return Ok(None);
+            }
+            let seconds = if version[0] == 1 {
+                let mut seconds = [0; 8];
+                file.read_exact(&mut seconds)?;
+                u64::from_be_bytes(seconds)
+            } else {
+                let mut seconds = [0; 4];
+                file.read_exact(&mut seconds)?;
+                u64::from(u32::from_be_bytes(seconds))
+            };
+
+            // Zero is used when the creation time is unknown.
+            if seconds == 0 {
+                return Ok(None);
+            }
+
+            let timestamp = i64::try_from(seconds).unwrap_or(i64::MAX) - QUICKTIME_EPOCH_OFFSET;
+            Ok(DateTime::from_timestamp(timestamp, 0)
+                .map(|date_time| TimestampValue::OffsetDateTime(date_time.fixed_offset())))
+        };
+
+        match read() {
+            // Truncated files are broken metadata too.
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            result => result.map_err(|source| io_error(path, source)),
+        }
+    }
+}
+
+/// Move to the content of the next box of that type before `end`, return its size. Boxes going
+/// past `end` end the search, their sizes cannot be trusted.
+fn find_box(
+    reader: &mut (impl Read + Seek),
+    end: u64,
+    box_type: &[u8; 4],
+) -> io::Result<Option<u64>> {
+    loop {
+        let start = reader.stream_position()?;
+        if start
+            .checked_add(8)
+            .is_none_or(|header_end| header_end > end)
+        {
+            return Ok(None);
+        }
+
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let mut size = u64::from(u32::from_be_bytes([
+            header[0], header[1], header[2], header[3],
+        ]));
+        let mut header_size = 8;
+
+        match size {
+            // The box goes to the end.
+            0 => size = end - start,
+            // The size does not fit in 32 bits.
+            1 => {
+                let mut large_size = [0; 8];
+                reader.read_exact(&mut large_size)?;
+                size = u64::from_be_bytes(large_size);
+                header_size = 16;
+            }
+            _ => {}
+        }
+
+        let Some(box_end) = start.checked_add(size).filter(|box_end| *box_end <= end) else {
+            return Ok(None);
+        };
+        if size < header_size {
+            return Ok(None);
+        }
+
+        if &header[4..] == box_type {
+            return Ok(Some(size - header_size));
+        }
+
+        reader.seek(SeekFrom::Start(box_end))?;
+    }
+}
+
+/// `CreationDate` of the document information of PDF files, such as `D:20221027153500+02'00'`.
+pub struct PdfExtractor;
+
+impl TimestampExtractor for PdfExtractor {
+    fn handles(&self, extension: &str) -> bool {
+        extension == "pdf"
+    }
+
+    fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError> {
+        // The document information is often at the end, after incremental updates.
+        let read = || -> io::Result<Option<TimestampValue>> {
+            let mut file = File::open(path)?;
+            let mut content = Vec::new();
+            (&mut file).take(READ_LIMIT).read_to_end(&mut content)?;
+            if let Some(value) = find_pdf_creation_date(&content) {
+                return Ok(Some(value));
+            }
+
+            let end = file.seek(SeekFrom::End(0))?;
+            if end <= READ_LIMIT {
+                return Ok(None);
+            }
+            file.seek(SeekFrom::Start(
+                end.saturating_sub(READ_LIMIT).max(READ_LIMIT),
+            ))?;
+            content.clear();
+            file.take(READ_LIMIT).read_to_end(&mut content)?;
+            Ok(find_pdf_creation_date(&content))
+        };
+
+        read().map_err(|source| io_error(path, source))
+    }
+}
+
+fn find_pdf_creation_date(content: &[u8]) -> Option<TimestampValue> {
+    // Compressed object streams are not looked into, the date is usually outside of them.
+    let start = find(content, b"/CreationDate")?;
+    let rest = &content[start + b"/CreationDate".len()..];
+    let open = rest.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    if rest[open] != b'(' {
+        return None;
+    }
+    let close = rest[open..].iter().position(|byte| *byte == b')')?;
+
+    std::str::from_utf8(&rest[open + 1..open + close])
+        .ok()
+        .and_then(parse_pdf_date)
+}
+
+/// `D:YYYYMMDDHHmmSSOHH'mm'`, every part after the year is optional.
+fn parse_pdf_date(text: &str) -> Option<TimestampValue> {
+    let text = text.strip_prefix("D:").unwrap_or(text);
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (numbers, offset) = text.split_at(digits);
+
+    let field = |range: std::ops::Range<usize>, default: u32| {
+        numbers
+            .get(range)
+            .map_or(Some(default), |number| number.parse().ok())
+    };
+    let year = numbers.get(0..4)?.parse().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, field(4..6, 1)?, field(6..8, 1)?)?;
+    let date_time = date.and_hms_opt(field(8..10, 0)?, field(10..12, 0)?, field(12..14, 0)?)?;
+
+    let offset = parse_pdf_offset(offset);
+
+    Some(
+        match offset.and_then(|offset| offset.from_local_datetime(&date_time).single()) {
+            Some(date_time) => TimestampValue::OffsetDateTime(date_time),
+            None => TimestampValue::DateTime(date_time),
+        },
+    )
+}
+
+/// `Z`, `+HH'mm'` or `-HH'mm'`, `None` when malformed.
+fn parse_pdf_offset(offset: &str) -> Option<FixedOffset> {
+    let sign = match offset.chars().next()? {
+        'Z' => return FixedOffset::east_opt(0),
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+
+    let mut parts = offset[1..].split('\'').filter(|part| !part.is_empty());
+    let mut part = |range: std::ops::RangeInclusive<i32>| {
+        parts.next().map_or(Some(0), |part| {
+            part.parse().ok().filter(|number| range.contains(number))
+        })
+    };
+    let (hours, minutes) = (part(0..=23)?, part(0..=59)?);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The XMP packet that many pictures and documents carry: `xmp:CreateDate`,
+/// `exif:DateTimeOriginal` or `photoshop:DateCreated`.
+pub struct XmpExtractor;
+
+const XMP_PROPERTIES: [&str; 3] = [
+    "exif:DateTimeOriginal",
+    "photoshop:DateCreated",
+    "xmp:CreateDate",
+];
+
+impl TimestampExtractor for XmpExtractor {
+    fn handles(&self, extension: &str) -> bool {
+        ExifExtractor.handles(extension) || matches!(extension, "pdf" | "gif" | "svg")
+    }
+
+    fn extract(&self, path: &Utf8Path) -> Result<Option<TimestampValue>, ExtractError> {
+        // The packet is near the start of pictures, big files are not read whole.
+        let mut content = Vec::new();
+        File::open(path)
+            .and_then(|file| file.take(READ_LIMIT).read_to_end(&mut content))
+            .map_err(|source| io_error(path, source))?;
+
+        for property in XMP_PROPERTIES {
+            let Some(start) = find(&content, property.as_bytes()) else {
+                continue;
+            };
+            // Either an attribute, `property="value"`, or an element, `<property>value<`.
+            let rest = &content[start + property.len()..];
+            let value = match rest.first() {
+                Some(b'=') => rest.get(2..).and_then(|rest| {
+                    let end = rest
+                        .iter()
+                        .position(|byte| *byte == b'"' || *byte == b'\'')?;
+                    Some(&rest[..end])
+                }),
+                Some(b'>') => {
+                    let rest = &rest[1..];
+                    rest.iter()
+                        .position(|byte| *byte == b'<')
+                        .map(|end| &rest[..end])
+                }
+                _ => None,
+            };
+
+            if let Some(value) = value
+                .and_then(|value| std::str::from_utf8(value).ok())
+                .and_then(|value| parse_iso_date(value.trim()))
+            {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The ISO 8601 dates of XMP, with or without time and offset.
+fn parse_iso_date(text: &str) -> Option<TimestampValue> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+        return Some(TimestampValue::OffsetDateTime(date_time));
+    }
+
+    for format in ["%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M%:z"] {
+        if let Ok(date_time) = DateTime::parse_from_str(text, format) {
+            return Some(TimestampValue::OffsetDateTime(date_time));
+        }
+    }
+
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(TimestampValue::DateTime(date_time));
+        }
+    }
+
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .map(TimestampValue::Date)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn io_error(path: &Utf8Path, source: io::Error) -> ExtractError {
+    ExtractError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
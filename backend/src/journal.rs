@@ -0,0 +1,255 @@
+//! Every rename is appended to a journal, one JSON object per line, so it can be undone later.
+//! Renames done by the same run of tagger share a session identifier.
+
+use crate::{RenameError, Renamer};
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path,
+};
+use thiserror::Error;
+
+/// One rename, as stored in the journal. Paths are absolute.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JournalEntry {
+    pub session: String,
+    pub time: NaiveDateTime,
+    pub original_path: Utf8PathBuf,
+    pub new_path: Utf8PathBuf,
+}
+
+/// Which renames to undo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UndoSelection {
+    /// Every rename of the most recent session.
+    LastSession,
+    /// The given number of renames, the most recent first, whatever their session.
+    Last(usize),
+    /// Every rename of a given session.
+    Session(String),
+}
+
+/// Everything that can go wrong with the journal.
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("no directory to store the journal in")]
+    NoStateDirectory,
+    #[error("cannot access the journal {path}")]
+    Io {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid journal entry at line {line}")]
+    Parse {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("cannot undo the rename of {original_path}")]
+    Undo {
+        original_path: Utf8PathBuf,
+        #[source]
+        source: RenameError,
+    },
+    /// The renames undone before the failure are no longer in the journal.
+    #[error("cannot undo every rename")]
+    PartialUndo {
+        undone: Vec<JournalEntry>,
+        #[source]
+        source: Box<JournalError>,
+    },
+}
+
+/// The journal file and the session of the current run.
+#[derive(Debug)]
+pub struct Journal {
+    path: Utf8PathBuf,
+    session: String,
+}
+
+impl Journal {
+    /// `tagger/journal.jsonl` in the user state directory (`$XDG_STATE_HOME` on Linux), or in
+    /// the local data directory on systems without state directory.
+    pub fn open_default() -> Result<Journal, JournalError> {
+        let directory = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .and_then(|directory| Utf8PathBuf::from_path_buf(directory).ok())
+            .ok_or(JournalError::NoStateDirectory)?;
+
+        Ok(Journal::new(directory.join("tagger").join("journal.jsonl")))
+    }
+
+    /// A journal with a new session.
+    pub fn new(path: Utf8PathBuf) -> Journal {
+        let session = Local::now().format("%Y%m%d-%H%M%S%3f").to_string();
+        Journal::with_session(path, session)
+    }
+
+    pub fn with_session(path: Utf8PathBuf, session: String) -> Journal {
+        Journal { path, session }
+    }
+
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Append a rename that just happened to the journal.
+    pub fn record(
+        &self,
+        original_path: &Utf8Path,
+        new_path: &Utf8Path,
+    ) -> Result<(), JournalError> {
+        let entry = JournalEntry {
+            session: self.session.clone(),
+            time: Local::now().naive_local(),
+            original_path: absolute(original_path).map_err(|source| self.io_error(source))?,
+            new_path: absolute(new_path).map_err(|source| self.io_error(source))?,
+        };
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory).map_err(|source| self.io_error(source))?;
+        }
+
+        let mut line = serde_json::to_string(&entry).expect("A journal entry is always valid JSON");
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|source| self.io_error(source))
+    }
+
+    /// Every rename in the journal, the oldest first. A missing journal is an empty one.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, JournalError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(self.io_error(source)),
+        };
+
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|source| JournalError::Parse {
+                    line: index + 1,
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Rename files back, the most recent rename first. Undone renames are removed from the
+    /// journal, even when a later one fails, and are then returned with the error. Return the
+    /// undone renames.
+    pub fn undo(&self, selection: &UndoSelection) -> Result<Vec<JournalEntry>, JournalError> {
+        let mut remaining = self.entries()?;
+
+        let session = match selection {
+            UndoSelection::LastSession => remaining.last().map(|entry| entry.session.clone()),
+            UndoSelection::Last(_) => None,
+            UndoSelection::Session(session) => Some(session.clone()),
+        };
+        let limit = match selection {
+            UndoSelection::Last(count) => *count,
+            _ => usize::MAX,
+        };
+
+        let mut undone = Vec::new();
+        let mut result = Ok(());
+
+        let mut index = remaining.len();
+        while index > 0 && undone.len() < limit {
+            index -= 1;
+            let entry = &remaining[index];
+            if session
+                .as_ref()
+                .is_some_and(|session| &entry.session != session)
+            {
+                continue;
+            }
+
+            if let Err(error) = undo_entry(entry) {
+                result = Err(error);
+                break;
+            }
+            undone.push(remaining.remove(index));
+        }
+
+        if undone.is_empty() {
+            return result.map(|_| undone);
+        }
+
+        self.rewrite(&remaining)?;
+        match result {
+            Ok(()) => Ok(undone),
+            Err(error) => Err(JournalError::PartialUndo {
+                undone,
+                source: Box::new(error),
+            }),
+        }
+    }
+
+    fn rewrite(&self, entries: &[JournalEntry]) -> Result<(), JournalError> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(
+                &serde_json::to_string(entry).expect("A journal entry is always valid JSON"),
+            );
+            content.push('\n');
+        }
+
+        // Write aside then swap, to never lose the journal halfway.
+        let temporary = self.path.with_extension("jsonl.tmp");
+        fs::write(&temporary, content)
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|source| self.io_error(source))
+    }
+
+    fn io_error(&self, source: io::Error) -> JournalError {
+        JournalError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+fn undo_entry(entry: &JournalEntry) -> Result<(), JournalError> {
+    let undo_error = |source| JournalError::Undo {
+        original_path: entry.original_path.clone(),
+        source,
+    };
+
+    if fs::symlink_metadata(&entry.new_path).is_err() {
+        return Err(undo_error(RenameError::SourceMissing {
+            path: entry.new_path.clone(),
+        }));
+    }
+
+    if crate::is_taken(&entry.new_path, &entry.original_path) {
+        return Err(undo_error(RenameError::Collision {
+            path: entry.original_path.clone(),
+        }));
+    }
+
+    Renamer::default()
+        .rename_path(&entry.new_path, &entry.original_path)
+        .map_err(undo_error)
+}
+
+fn absolute(path: &Utf8Path) -> io::Result<Utf8PathBuf> {
+    let absolute = path::absolute(path)?;
+    Utf8PathBuf::from_path_buf(absolute)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8"))
+}
@@ -1,11 +1,29 @@
+pub mod configuration_file;
+pub mod extract;
+pub mod journal;
+pub mod query;
+pub mod rename;
+pub mod statistics;
 pub mod structured_name;
 pub mod structured_path;
 pub mod tags;
 #[cfg(test)]
 pub mod tests;
 pub mod timestamp;
+pub mod transaction;
+pub mod vocabulary;
+pub mod walk;
 
+pub use configuration_file::*;
+pub use extract::*;
+pub use journal::*;
+pub use query::*;
+pub use rename::*;
+pub use statistics::*;
 pub use structured_name::*;
 pub use structured_path::*;
 pub use tags::*;
 pub use timestamp::*;
+pub use transaction::*;
+pub use vocabulary::*;
+pub use walk::*;
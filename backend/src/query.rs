@@ -0,0 +1,219 @@
+//! Select files/directories from their parsed names: tags, timestamp and file name.
+
+use crate::StructuredName;
+use chrono::NaiveDate;
+use std::{iter::Peekable, str::FromStr, vec::IntoIter};
+use thiserror::Error;
+
+/// A boolean expression on tags, such as `work AND NOT (draft OR old)`.
+///
+/// Keywords are upper case, `AND` may be omitted: `work NOT draft` is the same as
+/// `work AND NOT draft`. `NOT` binds tighter than `AND`, which binds tighter than `OR`. Tags
+/// containing whitespaces or parentheses are written between double quotes: `"client name"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagExpression {
+    Tag(String),
+    Not(Box<TagExpression>),
+    And(Box<TagExpression>, Box<TagExpression>),
+    Or(Box<TagExpression>, Box<TagExpression>),
+}
+
+/// Everything that can go wrong while parsing a tag expression.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("the tag expression is empty")]
+    Empty,
+    #[error("the tag expression ends too early")]
+    UnexpectedEnd,
+    #[error("unexpected `{0}` in the tag expression")]
+    UnexpectedToken(String),
+    #[error("a quote is not closed in the tag expression")]
+    UnterminatedQuote,
+}
+
+impl TagExpression {
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        self.matches_with(&|tag| tags.contains(&tag))
+    }
+
+    /// Same as [TagExpression::matches] with another way to tell whether a name has a tag.
+    pub fn matches_with(&self, has_tag: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            TagExpression::Tag(tag) => has_tag(tag),
+            TagExpression::Not(expression) => !expression.matches_with(has_tag),
+            TagExpression::And(left, right) => {
+                left.matches_with(has_tag) && right.matches_with(has_tag)
+            }
+            TagExpression::Or(left, right) => {
+                left.matches_with(has_tag) || right.matches_with(has_tag)
+            }
+        }
+    }
+}
+
+impl FromStr for TagExpression {
+    type Err = QueryError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(expression)?.into_iter().peekable();
+
+        if tokens.peek().is_none() {
+            return Err(QueryError::Empty);
+        }
+
+        let parsed = parse_or(&mut tokens)?;
+        match tokens.next() {
+            Some(token) => Err(QueryError::UnexpectedToken(token)),
+            None => Ok(parsed),
+        }
+    }
+}
+
+type Tokens = Peekable<IntoIter<String>>;
+
+/// Parentheses are tokens of their own, everything else is split by whitespaces, except between
+/// double quotes. Every quote must be closed.
+fn tokenize(expression: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in expression.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if quoted {
+            current.push(c);
+        } else if c.is_whitespace() || c == '(' || c == ')' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            current.push(c);
+        }
+    }
+
+    if quoted {
+        return Err(QueryError::UnterminatedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_or(tokens: &mut Tokens) -> Result<TagExpression, QueryError> {
+    let mut expression = parse_and(tokens)?;
+
+    while tokens.next_if(|token| token == "OR").is_some() {
+        let right = parse_and(tokens)?;
+        expression = TagExpression::Or(Box::new(expression), Box::new(right));
+    }
+
+    Ok(expression)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<TagExpression, QueryError> {
+    let mut expression = parse_not(tokens)?;
+
+    loop {
+        if tokens.next_if(|token| token == "AND").is_none() {
+            // `AND` may be omitted between two operands.
+            match tokens.peek().map(String::as_str) {
+                None | Some("OR") | Some(")") => break,
+                Some(_) => {}
+            }
+        }
+
+        let right = parse_not(tokens)?;
+        expression = TagExpression::And(Box::new(expression), Box::new(right));
+    }
+
+    Ok(expression)
+}
+
+fn parse_not(tokens: &mut Tokens) -> Result<TagExpression, QueryError> {
+    if tokens.next_if(|token| token == "NOT").is_some() {
+        return Ok(TagExpression::Not(Box::new(parse_not(tokens)?)));
+    }
+
+    match tokens.next() {
+        None => Err(QueryError::UnexpectedEnd),
+        Some(token) if token == "(" => {
+            let expression = parse_or(tokens)?;
+            match tokens.next() {
+                Some(token) if token == ")" => Ok(expression),
+                Some(token) => Err(QueryError::UnexpectedToken(token)),
+                None => Err(QueryError::UnexpectedEnd),
+            }
+        }
+        Some(token) if matches!(token.as_str(), ")" | "AND" | "OR") => {
+            Err(QueryError::UnexpectedToken(token))
+        }
+        Some(token) => Ok(TagExpression::Tag(token)),
+    }
+}
+
+/// Every criterion must match. Empty criteria match everything.
+///
+/// Tags match their ancestors in the vocabulary too: `finance` matches a name tagged `invoice`
+/// when `finance` is the parent of `invoice`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    pub tags: Option<TagExpression>,
+    /// Names without date (no timestamp or a time alone) do not match when there is a date
+    /// range. Periods match when any of their days is in the range.
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    /// Case-insensitive text looked for in the file name, without timestamp, tags or extension.
+    pub name: Option<String>,
+}
+
+impl Query {
+    pub fn matches(&self, name: &StructuredName) -> bool {
+        if let Some(expression) = &self.tags {
+            let tag_configuration = &name.configuration.tag_configuration;
+            let tags: Vec<_> = name
+                .tags
+                .iter()
+                .flat_map(|tags| tags.iter())
+                .flat_map(|tag| tag_configuration.with_ancestors(tag))
+                .collect();
+            let has_tag = |tag: &str| {
+                tags.iter()
+                    .any(|existing| tag_configuration.matches_tag(existing, tag))
+            };
+            if !expression.matches_with(&has_tag) {
+                return false;
+            }
+        }
+
+        if self.since.is_some() || self.until.is_some() {
+            let Some((first_day, last_day)) = name
+                .timestamp
+                .as_ref()
+                .and_then(|timestamp| Some((timestamp.value.date()?, timestamp.value.last_day()?)))
+            else {
+                return false;
+            };
+
+            if self.since.is_some_and(|since| last_day < since)
+                || self.until.is_some_and(|until| first_day > until)
+            {
+                return false;
+            }
+        }
+
+        if let Some(text) = &self.name {
+            let filename = name.filename.as_deref().unwrap_or_default();
+            if !filename.to_lowercase().contains(&text.to_lowercase()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
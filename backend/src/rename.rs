@@ -0,0 +1,312 @@
+//! Turn an edited [StructuredPath] into a rename on the disk.
+
+use crate::StructuredPath;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+};
+use thiserror::Error;
+
+/// What to do when the new name is already taken by another file/directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionStrategy {
+    /// Stop with [RenameError::Collision].
+    #[default]
+    Fail,
+    /// Leave the file/directory as is.
+    Skip,
+    /// Append a counter after the file name, before the tags: `name (2) -- tag.txt`.
+    Counter,
+    /// Replace what was there.
+    Overwrite,
+}
+
+/// What happened, or would happen, to a path.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RenameOutcome<'p, 'c> {
+    /// The new name is the same as the old one.
+    Unchanged,
+    /// The new name is taken and the strategy is [CollisionStrategy::Skip].
+    Skipped,
+    /// The final path, it may differ from the edited one with [CollisionStrategy::Counter].
+    Renamed(StructuredPath<'p, 'c>),
+}
+
+/// Everything that can go wrong while renaming.
+#[derive(Debug, Error)]
+pub enum RenameError {
+    #[error("{path} does not exist")]
+    SourceMissing { path: Utf8PathBuf },
+    #[error("{path} already exists")]
+    Collision { path: Utf8PathBuf },
+    #[error("{from} and {to} are not on the same device")]
+    CrossDevice { from: Utf8PathBuf, to: Utf8PathBuf },
+    #[error("cannot rename {from} to {to}")]
+    Io {
+        from: Utf8PathBuf,
+        to: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Renames files/directories, see [Renamer::rename].
+#[derive(Clone, Debug, Default)]
+pub struct Renamer {
+    pub collision: CollisionStrategy,
+    /// Moving across devices is a copy followed by a removal, so it is not atomic.
+    pub allow_cross_device: bool,
+}
+
+impl Renamer {
+    /// Compute what [Renamer::rename] would do, without touching anything.
+    pub fn plan<'p, 'c>(
+        &self,
+        path: &StructuredPath<'p, 'c>,
+    ) -> Result<RenameOutcome<'p, 'c>, RenameError> {
+        let from = path.original_path;
+        let to = Utf8PathBuf::from(path.to_string());
+
+        if to == from {
+            return Ok(RenameOutcome::Unchanged);
+        }
+
+        if fs::symlink_metadata(from).is_err() {
+            return Err(RenameError::SourceMissing {
+                path: from.to_path_buf(),
+            });
+        }
+
+        if !is_taken(from, &to) {
+            return Ok(RenameOutcome::Renamed(path.clone()));
+        }
+
+        match self.collision {
+            CollisionStrategy::Fail => Err(RenameError::Collision { path: to }),
+            CollisionStrategy::Skip => Ok(RenameOutcome::Skipped),
+            CollisionStrategy::Overwrite => Ok(RenameOutcome::Renamed(path.clone())),
+            CollisionStrategy::Counter => {
+                for counter in 2.. {
+                    let mut candidate = path.clone();
+                    candidate.name.push_suffix(&format!(" ({})", counter));
+
+                    let candidate_path = Utf8PathBuf::from(candidate.to_string());
+                    if candidate_path == from || !is_taken(from, &candidate_path) {
+                        return Ok(RenameOutcome::Renamed(candidate));
+                    }
+                }
+                unreachable!("There is always a free counter")
+            }
+        }
+    }
+
+    /// Rename the original path of a structured path to its `Display`, following the collision
+    /// strategy.
+    pub fn rename<'p, 'c>(
+        &self,
+        path: &StructuredPath<'p, 'c>,
+    ) -> Result<RenameOutcome<'p, 'c>, RenameError> {
+        let outcome = self.plan(path)?;
+
+        if let RenameOutcome::Renamed(renamed) = &outcome {
+            self.rename_path(
+                renamed.original_path,
+                &Utf8PathBuf::from(renamed.to_string()),
+            )?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// The rename itself, once collisions are dealt with. `to` is only overwritten with
+    /// [CollisionStrategy::Overwrite], even when it appeared after the collision checks.
+    pub fn rename_path(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), RenameError> {
+        let io_error = |source: io::Error| match source.kind() {
+            io::ErrorKind::AlreadyExists => RenameError::Collision {
+                path: to.to_path_buf(),
+            },
+            _ => RenameError::Io {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                source,
+            },
+        };
+        let overwrite = self.collision == CollisionStrategy::Overwrite;
+
+        // On case-insensitive filesystems, `to` may be `from` with another case. Going through
+        // a temporary name makes sure the new case is applied.
+        if to.exists() && is_same_file(from, to) {
+            let temporary = from.with_file_name(format!(
+                ".{}.tagger-tmp",
+                from.file_name().unwrap_or_default()
+            ));
+            fs::rename(from, &temporary).map_err(io_error)?;
+            return fs::rename(&temporary, to).map_err(io_error);
+        }
+
+        let cross_device_error = || RenameError::CrossDevice {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+
+        if !self.allow_cross_device && !same_device(from, to) {
+            return Err(cross_device_error());
+        }
+
+        let renamed = if overwrite {
+            fs::rename(from, to)
+        } else {
+            rename_no_replace(from, to)
+        };
+
+        match renamed {
+            Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+                if !self.allow_cross_device {
+                    return Err(cross_device_error());
+                }
+                // Only files can be moved that way.
+                if !from.is_file() {
+                    return Err(io_error(error));
+                }
+                copy(from, to, overwrite)
+                    .and_then(|_| fs::remove_file(from))
+                    .map_err(io_error)
+            }
+            result => result.map_err(io_error),
+        }
+    }
+}
+
+/// Move `from` to `to`, failing with [io::ErrorKind::AlreadyExists] instead of replacing what is
+/// there.
+fn rename_no_replace(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    match system_rename_no_replace(from, to) {
+        // Not supported by the system or the filesystem.
+        Err(error)
+            if matches!(
+                error.kind(),
+                io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported
+            ) => {}
+        result => return result,
+    }
+
+    // A hard link is never made over something, but only files can have one.
+    if fs::symlink_metadata(from)?.is_file() {
+        match fs::hard_link(from, to) {
+            Ok(()) => return fs::remove_file(from),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => return Err(error),
+            Err(_) => {}
+        }
+    }
+
+    // What is left cannot be moved without a small window between the check and the rename.
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::ErrorKind::AlreadyExists.into());
+    }
+    fs::rename(from, to)
+}
+
+#[cfg(target_os = "linux")]
+fn system_rename_no_replace(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    let (from, to) = (c_path(from)?, c_path(to)?);
+    // SAFETY: both paths are nul-terminated and outlive the call.
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            from.as_ptr(),
+            libc::AT_FDCWD,
+            to.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn system_rename_no_replace(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    let (from, to) = (c_path(from)?, c_path(to)?);
+    // SAFETY: both paths are nul-terminated and outlive the call.
+    let result = unsafe { libc::renamex_np(from.as_ptr(), to.as_ptr(), libc::RENAME_EXCL) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn system_rename_no_replace(_from: &Utf8Path, _to: &Utf8Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn c_path(path: &Utf8Path) -> io::Result<std::ffi::CString> {
+    std::ffi::CString::new(path.as_str())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+/// Copy a file across devices, without replacing what is there unless asked.
+fn copy(from: &Utf8Path, to: &Utf8Path, overwrite: bool) -> io::Result<()> {
+    if overwrite {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    let mut source = File::open(from)?;
+    let mut target = OpenOptions::new().write(true).create_new(true).open(to)?;
+    io::copy(&mut source, &mut target)?;
+    target.set_permissions(source.metadata()?.permissions())
+}
+
+/// Whether `to` is taken by something else than `from`.
+pub fn is_taken(from: &Utf8Path, to: &Utf8Path) -> bool {
+    fs::symlink_metadata(to).is_ok() && !is_same_file(from, to)
+}
+
+#[cfg(unix)]
+fn is_same_file(first: &Utf8Path, second: &Utf8Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::symlink_metadata(first), fs::symlink_metadata(second)) {
+        (Ok(first), Ok(second)) => first.dev() == second.dev() && first.ino() == second.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(first: &Utf8Path, second: &Utf8Path) -> bool {
+    // Canonical paths use the case stored on the disk.
+    match (first.canonicalize_utf8(), second.canonicalize_utf8()) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => false,
+    }
+}
+
+/// Compare the device of the source with the one of the directory receiving it.
+#[cfg(unix)]
+fn same_device(from: &Utf8Path, to: &Utf8Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let directory = match to.parent() {
+        Some(parent) if !parent.as_str().is_empty() => parent,
+        _ => Utf8Path::new("."),
+    };
+
+    match (fs::symlink_metadata(from), fs::metadata(directory)) {
+        (Ok(from), Ok(directory)) => from.dev() == directory.dev(),
+        // Let the rename itself report the error.
+        _ => true,
+    }
+}
+
+/// Renaming across devices fails on its own with [io::ErrorKind::CrossesDevices].
+#[cfg(not(unix))]
+fn same_device(_from: &Utf8Path, _to: &Utf8Path) -> bool {
+    true
+}
@@ -0,0 +1,110 @@
+//! Gather how tags are used across many names, to keep the vocabulary consistent.
+
+use crate::{configuration::TimestampConfiguration, StructuredPath, TimestampValue};
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How one tag is used.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TagStatistics {
+    /// Number of names with this tag.
+    pub count: usize,
+    /// The oldest timestamp among the names with this tag, times alone are ignored.
+    pub first: Option<TimestampValue>,
+    /// The most recent timestamp among the names with this tag.
+    pub last: Option<TimestampValue>,
+    /// The directories containing the names with this tag.
+    pub directories: BTreeSet<Utf8PathBuf>,
+    /// Key/value tags are counted on their key, with the number of names for each value.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, usize>,
+    /// The instants of the first and last timestamps, the names may have different timezones.
+    #[serde(skip)]
+    first_instant: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    last_instant: Option<DateTime<Utc>>,
+}
+
+/// Every distinct tag, sorted alphabetically so that near-duplicates end up next to each other.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct TagReport {
+    pub tags: BTreeMap<String, TagStatistics>,
+}
+
+impl TagReport {
+    /// Count the tags of a parsed path.
+    pub fn add(&mut self, path: &StructuredPath) {
+        let Some(tags) = &path.name.tags else {
+            return;
+        };
+
+        let directory = match path.original_path.parent() {
+            Some(parent) if !parent.as_str().is_empty() => parent,
+            _ => Utf8Path::new("."),
+        };
+        // A time alone cannot be placed among the others.
+        let timestamp_configuration = &path.name.configuration.timestamp_configuration;
+        let timestamp = path.name.timestamp.as_ref().and_then(|timestamp| {
+            Some((
+                timestamp.value,
+                instant(timestamp.value, timestamp_configuration)?,
+            ))
+        });
+
+        // A tag repeated in a name is counted once, whatever its Unicode form.
+        let tag_configuration = &path.name.configuration.tag_configuration;
+        let mut distinct: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for tag in tags.iter() {
+            let tag = tag_configuration.normalise_text(tag);
+            match tag_configuration.split_key_value(&tag) {
+                Some((key, value)) => {
+                    let values = distinct.entry(key.to_string()).or_default();
+                    values.insert(value.to_string());
+                }
+                None => {
+                    distinct.entry(tag.into_owned()).or_default();
+                }
+            }
+        }
+
+        for (tag, values) in distinct {
+            let statistics = self.tags.entry(tag).or_default();
+            statistics.count += 1;
+            for value in values {
+                *statistics.values.entry(value).or_default() += 1;
+            }
+            statistics.directories.insert(directory.to_path_buf());
+
+            if let Some((timestamp, instant)) = timestamp {
+                if statistics.first_instant.is_none_or(|first| instant < first) {
+                    statistics.first = Some(timestamp);
+                    statistics.first_instant = Some(instant);
+                }
+                if statistics.last_instant.is_none_or(|last| instant > last) {
+                    statistics.last = Some(timestamp);
+                    statistics.last_instant = Some(instant);
+                }
+            }
+        }
+    }
+}
+
+/// Dates and the start of periods are considered at midnight to compare them with date-times.
+/// Date-times without offset are placed in the default timezone, or taken as UTC when they do
+/// not exist there.
+fn instant(value: TimestampValue, configuration: &TimestampConfiguration) -> Option<DateTime<Utc>> {
+    let date_time: NaiveDateTime = match value {
+        TimestampValue::OffsetDateTime(date_time) => return Some(date_time.with_timezone(&Utc)),
+        TimestampValue::DateTime(date_time) => date_time,
+        TimestampValue::Time(_) => return None,
+        _ => value.date()?.and_hms_opt(0, 0, 0)?,
+    };
+
+    Some(configuration.with_default_timezone(date_time).map_or_else(
+        || date_time.and_utc(),
+        |date_time| date_time.with_timezone(&Utc),
+    ))
+}
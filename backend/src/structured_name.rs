@@ -1,21 +1,35 @@
-use crate::{configuration::TimestampConfiguration, TagConfiguration, Tags, Timestamp};
+use crate::{
+    configuration::TimestampConfiguration, TagConfiguration, TagError, TagOrder, Tags, Timestamp,
+    TimestampValue,
+};
 use camino::Utf8Path;
-use std::fmt;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
 
 /// Serialisation/deserialisation configuration for file/directory names.
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Missing values are taken from the defaults, see [crate::configuration_file] for how it is
+/// loaded from the disk.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct StructuredNameConfiguration {
+    #[serde(rename = "timestamp")]
     pub timestamp_configuration: TimestampConfiguration,
+    #[serde(rename = "tags")]
     pub tag_configuration: TagConfiguration,
 }
 
 /// The result of a parsed name of a file/directory.
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Parsed parts borrow the path, edited parts are owned: see the `add_tag`, `remove_tag`,
+/// `set_timestamp`, `push_prefix` and `push_suffix` methods.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StructuredName<'p, 'c> {
     pub configuration: &'c StructuredNameConfiguration,
 
-    pub timestamp: Option<Timestamp<'c>>,
-    pub filename: Option<&'p str>,
+    pub timestamp: Option<Timestamp<'p, 'c>>,
+    pub filename: Option<Cow<'p, str>>,
     pub tags: Option<Tags<'p>>,
     pub extension: Option<&'p str>,
 }
@@ -29,26 +43,275 @@ impl<'p, 'c> StructuredName<'p, 'c> {
         let mut timestamp = None;
         let mut tags = None;
 
-        if let Some(mut name) = filename.as_mut() {
-            timestamp = configuration.timestamp_configuration.parse(&mut name);
-            tags = configuration.tag_configuration.parse(&mut name);
+        if let Some(name) = filename.as_mut() {
+            timestamp = configuration.timestamp_configuration.parse(name);
+            tags = configuration.tag_configuration.parse(name);
         }
 
         StructuredName {
             timestamp,
             tags,
-            filename,
+            filename: filename.map(Cow::Borrowed),
             extension: path.extension(),
             configuration,
         }
     }
 
     /// Normalisation is when we want to use the main format (listed first) to keep everything
-    /// tidy, instead of the one previously used.
-    pub fn normalise(&mut self) {
+    /// tidy, instead of the one previously used. It applies to the timestamp and to the tag
+    /// separators, duplicate and empty tags are removed and tags are sorted, see
+    /// [TagConfiguration::tag_order](crate::TagConfiguration::tag_order). Timestamps found by
+    /// the import patterns are moved to the front, see [StructuredName::import_timestamp].
+    ///
+    /// The name is left unchanged when one of its tags would not be read back once written with
+    /// the default separators.
+    pub fn normalise(&mut self) -> Result<(), TagError> {
+        self.normalise_with_order(self.configuration.tag_configuration.tag_order)
+    }
+
+    /// Same as [StructuredName::normalise] with another tag order than the configured one.
+    pub fn normalise_with_order(&mut self, order: TagOrder) -> Result<(), TagError> {
+        // Tags go first, they are the only part that may fail.
+        if let Some(tags) = self.tags.as_mut() {
+            tags.normalise(&self.configuration.tag_configuration, order)?;
+        }
+
+        self.import_timestamp();
+
         if let Some(timestamp) = self.timestamp.as_mut() {
             timestamp.normalise();
         }
+
+        Ok(())
+    }
+
+    /// Replace the timestamp, using the main format. When there was none, a space is added to
+    /// keep it apart from the file name.
+    pub fn set_timestamp(&mut self, value: TimestampValue) {
+        let had_timestamp = self.timestamp.is_some();
+        self.timestamp = Some(
+            self.configuration
+                .timestamp_configuration
+                .new_timestamp(value),
+        );
+
+        if had_timestamp {
+            return;
+        }
+
+        if let Some(filename) = self.filename.as_mut() {
+            let (separator, _) = split_leading_separator(filename);
+            if separator.is_empty() && !filename.is_empty() {
+                filename.to_mut().insert(0, ' ');
+            }
+        }
+    }
+
+    /// Move the timestamp found in the file name by the first matching import pattern to the
+    /// front, in the main format, apart from what is left of the file name. Return whether one
+    /// was found, names starting with a timestamp are left as they are.
+    pub fn import_timestamp(&mut self) -> bool {
+        if self.timestamp.is_some() {
+            return false;
+        }
+
+        let timestamp_configuration = &self.configuration.timestamp_configuration;
+        let Some((imported, remainder)) = self
+            .filename
+            .as_deref()
+            .and_then(|filename| timestamp_configuration.import(filename))
+        else {
+            return false;
+        };
+
+        self.filename = Some(Cow::Owned(remainder));
+        self.set_timestamp(imported.value);
+        true
+    }
+
+    /// Render the date-time in another timezone, with the main format of the date-times with an
+    /// offset. Date-times without offset are taken in the default timezone. Return whether the
+    /// timestamp changed: dates and times alone cannot be converted.
+    pub fn convert_timezone(&mut self, timezone: Tz) -> bool {
+        let timestamp_configuration = &self.configuration.timestamp_configuration;
+
+        let date_time = match self.timestamp.as_ref().map(|timestamp| timestamp.value) {
+            Some(TimestampValue::OffsetDateTime(date_time)) => date_time,
+            Some(TimestampValue::DateTime(date_time)) => {
+                match timestamp_configuration.with_default_timezone(date_time) {
+                    Some(date_time) => date_time,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+
+        let converted = date_time.with_timezone(&timezone).fixed_offset();
+        self.set_timestamp(TimestampValue::OffsetDateTime(converted));
+        true
+    }
+
+    /// Add text after the timestamp (and what separates it from the file name) and before the
+    /// file name. When there was nothing after the timestamp, a space is added to keep them
+    /// apart, as [StructuredName::set_timestamp] does.
+    pub fn push_prefix(&mut self, prefix: &str) {
+        let has_timestamp = self.timestamp.is_some();
+        let filename = self.filename.get_or_insert(Cow::Borrowed(""));
+
+        let index = if has_timestamp {
+            split_leading_separator(filename).0.len()
+        } else {
+            0
+        };
+
+        filename.to_mut().insert_str(index, prefix);
+
+        let (separator, _) = split_leading_separator(filename);
+        if has_timestamp && separator.is_empty() && !filename.is_empty() {
+            filename.to_mut().insert(0, ' ');
+        }
+    }
+
+    /// Add text after the file name and before the tags.
+    pub fn push_suffix(&mut self, suffix: &str) {
+        self.filename
+            .get_or_insert(Cow::Borrowed(""))
+            .to_mut()
+            .push_str(suffix);
+    }
+
+    /// Add a tag at the end of the tags, unless it's already there. New separators are the
+    /// default ones. Tags that would not be read back once written are refused.
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), TagError> {
+        let configuration = &self.configuration.tag_configuration;
+
+        match self.tags.as_mut() {
+            None => {
+                self.tags = Some(Tags::Tags(vec![configuration.new_tag(
+                    Cow::Owned(configuration.default_main_separator().to_string()),
+                    tag,
+                )?]));
+            }
+            Some(Tags::SeparatorOnly(separator)) => {
+                // Blanks kept after the separator would be read as an empty tag.
+                let separator = if configuration
+                    .tag_main_separators
+                    .iter()
+                    .any(|main| main == separator)
+                {
+                    separator.clone()
+                } else {
+                    Cow::Owned(configuration.default_main_separator().to_string())
+                };
+                let tag = configuration.new_tag(separator, tag)?;
+                self.tags = Some(Tags::Tags(vec![tag]));
+            }
+            Some(Tags::Tags(tags)) => {
+                if !tags
+                    .iter()
+                    .any(|existing| configuration.same_tag(&existing.tag, tag))
+                {
+                    tags.push(
+                        configuration
+                            .new_tag(Cow::Owned(configuration.default_between_separator()), tag)?,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the tags with the same key by this key/value tag, where the first one was, or add
+    /// it when there are none. Other tags are only added.
+    pub fn set_tag(&mut self, tag: &str) -> Result<(), TagError> {
+        let configuration = &self.configuration.tag_configuration;
+        let Some((key, _)) = configuration.split_key_value(tag) else {
+            return self.add_tag(tag);
+        };
+
+        let same_key: Vec<String> = self
+            .tags
+            .iter()
+            .flat_map(Tags::iter)
+            .filter(|existing| {
+                configuration
+                    .split_key_value(existing)
+                    .is_some_and(|(existing_key, _)| configuration.same_tag(existing_key, key))
+            })
+            .map(str::to_string)
+            .collect();
+        let same_key: Vec<_> = same_key.iter().map(String::as_str).collect();
+
+        if !self.replace_tags(&same_key, tag)? {
+            self.add_tag(tag)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove every occurrence of a tag. Return whether something was removed.
+    ///
+    /// When the first tag is removed, the next one takes its separator. When the last tag is
+    /// removed, the tag separator is removed too.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let Some(Tags::Tags(tags)) = self.tags.as_mut() else {
+            return false;
+        };
+
+        let main_separator = match tags.first() {
+            Some(first) => first.separator.clone(),
+            None => return false,
+        };
+
+        let configuration = &self.configuration.tag_configuration;
+        let count = tags.len();
+        tags.retain(|existing| !configuration.same_tag(&existing.tag, tag));
+        let removed = tags.len() != count;
+
+        if let Some(first) = tags.first_mut() {
+            first.separator = main_separator;
+        } else {
+            self.tags = None;
+        }
+
+        removed
+    }
+
+    /// Replace the first of the old tags found by the new one, where it was, and remove the
+    /// others. Return whether something changed.
+    ///
+    /// When the new tag is already there, the old tags are only removed.
+    pub fn replace_tags(&mut self, old: &[&str], new: &str) -> Result<bool, TagError> {
+        let Some(Tags::Tags(tags)) = self.tags.as_mut() else {
+            return Ok(false);
+        };
+
+        let configuration = &self.configuration.tag_configuration;
+        let old: Vec<_> = old
+            .iter()
+            .filter(|tag| !configuration.same_tag(tag, new))
+            .collect();
+        let Some(index) = tags.iter().position(|existing| {
+            old.iter()
+                .any(|tag| configuration.same_tag(&existing.tag, tag))
+        }) else {
+            return Ok(false);
+        };
+
+        if !tags
+            .iter()
+            .any(|existing| configuration.same_tag(&existing.tag, new))
+        {
+            let separator = tags[index].separator.clone();
+            tags[index] = configuration.new_tag(separator, new)?;
+        }
+
+        for tag in old {
+            self.remove_tag(tag);
+        }
+
+        Ok(true)
     }
 }
 
@@ -58,7 +321,7 @@ impl<'p, 'c> fmt::Display for StructuredName<'p, 'c> {
             write!(f, "{}", timestamp)?;
         }
 
-        if let Some(filename) = self.filename {
+        if let Some(filename) = &self.filename {
             write!(f, "{}", filename)?;
         }
 
@@ -73,3 +336,12 @@ impl<'p, 'c> fmt::Display for StructuredName<'p, 'c> {
         Ok(())
     }
 }
+
+/// Split the separator between the timestamp and the file name from the file name itself.
+fn split_leading_separator(filename: &str) -> (&str, &str) {
+    let index = filename
+        .find(|c: char| !(c.is_whitespace() || c == '-' || c == '_'))
+        .unwrap_or(filename.len());
+
+    filename.split_at(index)
+}
@@ -1,9 +1,9 @@
-use crate::{StructuredName, StructuredNameConfiguration};
+use crate::{StructuredName, StructuredNameConfiguration, TagError};
 use camino::Utf8Path;
 use std::fmt;
 
 /// The result of a parsed path.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StructuredPath<'p, 'c> {
     pub original_path: &'p Utf8Path,
     pub name: StructuredName<'p, 'c>,
@@ -22,8 +22,8 @@ impl<'p, 'c> StructuredPath<'p, 'c> {
 
     /// Normalisation is when we want to use the main format (listed first) to keep everything
     /// tidy, instead of the one previously used.
-    pub fn normalise(&mut self) {
-        self.name.normalise();
+    pub fn normalise(&mut self) -> Result<(), TagError> {
+        self.name.normalise()
     }
 }
 
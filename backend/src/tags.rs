@@ -1,15 +1,162 @@
+use crate::{TagCheck, TagVocabulary};
 use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cmp::Reverse};
+use thiserror::Error;
+use unicode_normalization::{is_nfc_quick, is_nfd_quick, IsNormalized, UnicodeNormalization};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Tags<'p> {
     Tags(Vec<Tag<'p>>),
-    SeparatorOnly(&'p str),
+    SeparatorOnly(Cow<'p, str>),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Parsed tags borrow the path, added tags are owned.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Tag<'p> {
-    pub separator: &'p str,
-    pub tag: &'p str,
+    pub separator: Cow<'p, str>,
+    /// The tag itself, without quotes.
+    pub tag: Cow<'p, str>,
+    /// The tag as written in the name, quotes included.
+    pub raw: Cow<'p, str>,
+}
+
+/// Everything that can go wrong while adding a tag to a name.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TagError {
+    #[error("the tag `{tag}` would not be read back the same once written in a name")]
+    Unwritable { tag: String },
+}
+
+/// How tags are sorted when a name is normalised.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagOrder {
+    /// As they are in the name.
+    #[default]
+    Keep,
+    /// Ignoring the case.
+    Alphabetical,
+    /// In the order of the allowed tags of the vocabulary, the others last.
+    Vocabulary,
+}
+
+impl<'p> Tags<'p> {
+    /// Sort the tags, the separators stay where they are. The vocabulary is compared in the
+    /// Unicode form.
+    pub fn sort(
+        &mut self,
+        order: TagOrder,
+        vocabulary: Option<&TagVocabulary>,
+        form: Option<UnicodeForm>,
+    ) {
+        let Tags::Tags(tags) = self else {
+            return;
+        };
+
+        let separators: Vec<_> = tags.iter().map(|tag| tag.separator.clone()).collect();
+
+        match order {
+            TagOrder::Keep => return,
+            TagOrder::Alphabetical => tags.sort_by_key(|tag| tag.tag.to_lowercase()),
+            TagOrder::Vocabulary => tags.sort_by_key(|tag| {
+                vocabulary
+                    .and_then(|vocabulary| vocabulary.position(&tag.tag, form))
+                    .unwrap_or(usize::MAX)
+            }),
+        }
+
+        for (tag, separator) in tags.iter_mut().zip(separators) {
+            tag.separator = separator;
+        }
+    }
+
+    /// Remove duplicate and empty tags, use the default separators and Unicode form then sort the
+    /// tags. The tags are left unchanged when one of them would not be read back once written
+    /// with the default separators, such as `+` between ` ` and ` + `.
+    pub(crate) fn normalise(
+        &mut self,
+        configuration: &TagConfiguration,
+        order: TagOrder,
+    ) -> Result<(), TagError> {
+        let main_separator = Cow::Owned(configuration.default_main_separator().to_string());
+
+        match self {
+            Tags::SeparatorOnly(separator) => *separator = main_separator,
+            Tags::Tags(tags) => {
+                // Doubled separators give empty tags, blank ones are empty too. Only the first
+                // occurrence of a tag is kept, with quotes only where they are needed. Nothing
+                // changes until every tag is known to be writable.
+                let mut normalised: Vec<Option<(String, String)>> = Vec::new();
+                for tag in tags.iter() {
+                    let text = configuration.normalise_text(&tag.tag);
+                    let seen = normalised.iter().flatten().any(|(kept, _)| *kept == text);
+                    if text.trim().is_empty() || seen {
+                        normalised.push(None);
+                        continue;
+                    }
+
+                    let raw = configuration
+                        .quote_tag(&text)
+                        .ok_or_else(|| TagError::Unwritable {
+                            tag: text.to_string(),
+                        })?
+                        .into_owned();
+                    normalised.push(Some((text.into_owned(), raw)));
+                }
+
+                let mut normalised = normalised.into_iter();
+                tags.retain_mut(|tag| match normalised.next().flatten() {
+                    Some((text, raw)) => {
+                        tag.tag = Cow::Owned(text);
+                        tag.raw = Cow::Owned(raw);
+                        true
+                    }
+                    None => false,
+                });
+
+                if tags.is_empty() {
+                    *self = Tags::SeparatorOnly(main_separator);
+                    return Ok(());
+                }
+
+                let between_separator = configuration.default_between_separator();
+                for (index, tag) in tags.iter_mut().enumerate() {
+                    tag.separator = if index == 0 {
+                        main_separator.clone()
+                    } else {
+                        Cow::Owned(between_separator.clone())
+                    };
+                }
+
+                self.sort(
+                    order,
+                    configuration.vocabulary.as_ref(),
+                    configuration.unicode_form,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The tags themselves, without separators.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        let tags = match self {
+            Tags::Tags(tags) => tags.as_slice(),
+            Tags::SeparatorOnly(_) => &[],
+        };
+
+        tags.iter().map(|tag| tag.tag.as_ref())
+    }
+}
+
+impl<'p> Tag<'p> {
+    /// The key and the value of a tag such as `status=final`, see
+    /// [TagConfiguration::key_value_delimiters].
+    pub fn key_value(&self, configuration: &TagConfiguration) -> Option<(&str, &str)> {
+        configuration.split_key_value(&self.tag)
+    }
 }
 
 impl<'p> fmt::Display for Tags<'p> {
@@ -17,7 +164,7 @@ impl<'p> fmt::Display for Tags<'p> {
         match self {
             Tags::Tags(tags) => {
                 for tag in tags {
-                    write!(f, "{}{}", tag.separator, tag.tag)?;
+                    write!(f, "{}{}", tag.separator, tag.raw)?;
                 }
             }
             Tags::SeparatorOnly(sep) => write!(f, "{}", sep)?,
@@ -27,14 +174,210 @@ impl<'p> fmt::Display for Tags<'p> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Written around the tags containing a between-separator, such as `[client name]`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagQuotes {
+    pub open: String,
+    pub close: String,
+}
+
+/// Unicode normalisation forms, see [TagConfiguration::unicode_form].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    /// Composed, as usually typed on Linux and Windows.
+    Nfc,
+    /// Decomposed, as written by macOS.
+    Nfd,
+}
+
+/// The text in the form, borrowed when it already is or when there is no form.
+pub fn normalise_text(form: Option<UnicodeForm>, text: &str) -> Cow<'_, str> {
+    match form {
+        Some(UnicodeForm::Nfc) if is_nfc_quick(text.chars()) != IsNormalized::Yes => {
+            Cow::Owned(text.nfc().collect())
+        }
+        Some(UnicodeForm::Nfd) if is_nfd_quick(text.chars()) != IsNormalized::Yes => {
+            Cow::Owned(text.nfd().collect())
+        }
+        _ => Cow::Borrowed(text),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TagConfiguration {
     /// First separator is the default one.
+    #[serde(rename = "main_separators")]
     pub tag_main_separators: Vec<String>,
-    pub tag_between_separators: Vec<char>,
+    /// First separator is the default one. The longest wins when several match at once.
+    #[serde(rename = "between_separators")]
+    pub tag_between_separators: Vec<String>,
+    /// Every tag is allowed when there is no vocabulary.
+    pub vocabulary: Option<TagVocabulary>,
+    /// How to sort the tags when normalising.
+    #[serde(rename = "order")]
+    pub tag_order: TagOrder,
+    /// Tags are compared in this form, and written in it when normalising. They are compared as
+    /// they are when there is none.
+    pub unicode_form: Option<UnicodeForm>,
+    /// Tags cannot contain a between-separator when there are none.
+    pub quotes: Option<TagQuotes>,
+    /// Split tags such as `status=final` into a key and a value. Tags are not split when there
+    /// are none.
+    pub key_value_delimiters: Vec<String>,
+}
+
+/// Mirrors the defaults of [filetags](https://github.com/novoid/filetags).
+impl Default for TagConfiguration {
+    fn default() -> Self {
+        TagConfiguration {
+            tag_main_separators: vec![" -- ".to_string()],
+            tag_between_separators: vec![" ".to_string()],
+            vocabulary: None,
+            tag_order: TagOrder::Keep,
+            unicode_form: None,
+            quotes: None,
+            key_value_delimiters: vec![],
+        }
+    }
 }
 
 impl TagConfiguration {
+    /// Separator written before the first tag of a name without tags.
+    pub fn default_main_separator(&self) -> &str {
+        self.tag_main_separators
+            .first()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Separator written before any tag added after the first one.
+    pub fn default_between_separator(&self) -> String {
+        self.tag_between_separators
+            .first()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The tag in the configured Unicode form, borrowed when it already is.
+    pub fn normalise_text<'t>(&self, tag: &'t str) -> Cow<'t, str> {
+        normalise_text(self.unicode_form, tag)
+    }
+
+    /// The tag as written in a name, between quotes when it would not be read back otherwise.
+    /// `None` when it cannot be written so that it is read back, such as a tag needing quotes
+    /// but containing the closing one.
+    pub fn quote_tag<'t>(&self, tag: &'t str) -> Option<Cow<'t, str>> {
+        if self.reads_back(tag, tag) {
+            return Some(Cow::Borrowed(tag));
+        }
+
+        let quotes = self.quotes.as_ref()?;
+        if quotes.close.is_empty() || tag.contains(&quotes.close) {
+            return None;
+        }
+        let quoted = format!("{}{}{}", quotes.open, tag, quotes.close);
+        self.reads_back(&quoted, tag).then_some(Cow::Owned(quoted))
+    }
+
+    /// A new tag, quoted when needed.
+    pub fn new_tag<'p>(&self, separator: Cow<'p, str>, tag: &str) -> Result<Tag<'p>, TagError> {
+        let raw = self
+            .quote_tag(tag)
+            .ok_or_else(|| TagError::Unwritable {
+                tag: tag.to_string(),
+            })?
+            .into_owned();
+
+        Ok(Tag {
+            separator,
+            tag: Cow::Owned(tag.to_string()),
+            raw: Cow::Owned(raw),
+        })
+    }
+
+    /// Whether both are the same tag once in the configured Unicode form.
+    pub fn same_tag(&self, first: &str, second: &str) -> bool {
+        first == second || self.normalise_text(first) == self.normalise_text(second)
+    }
+
+    /// The key and the value of the tag, split at the first delimiter. Both must be there.
+    pub fn split_key_value<'t>(&self, tag: &'t str) -> Option<(&'t str, &'t str)> {
+        let (index, delimiter) = self
+            .key_value_delimiters
+            .iter()
+            .filter(|delimiter| !delimiter.is_empty())
+            .filter_map(|delimiter| Some((tag.find(delimiter.as_str())?, delimiter)))
+            .min_by_key(|(index, _)| *index)?;
+
+        let (key, value) = (&tag[..index], &tag[index + delimiter.len()..]);
+        (!key.is_empty() && !value.is_empty()).then_some((key, value))
+    }
+
+    /// Whether a tag of a name matches a tag looked for. A key alone matches every value of the
+    /// key, and key/value tags match whatever their delimiter.
+    pub fn matches_tag(&self, tag: &str, wanted: &str) -> bool {
+        if self.same_tag(tag, wanted) {
+            return true;
+        }
+
+        match (self.split_key_value(tag), self.split_key_value(wanted)) {
+            (Some((key, _)), None) => self.same_tag(key, wanted),
+            (Some((key, value)), Some((wanted_key, wanted_value))) => {
+                self.same_tag(key, wanted_key) && self.same_tag(value, wanted_value)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check a tag against the vocabulary, if any, both in the Unicode form. Key/value tags
+    /// unknown as a whole are checked on their key, their value is free.
+    pub fn check_tag(&self, tag: &str) -> TagCheck {
+        let Some(vocabulary) = &self.vocabulary else {
+            return TagCheck::Known;
+        };
+
+        let tag = self.normalise_text(tag);
+        let check = vocabulary.check(&tag, self.unicode_form);
+        let Some((key, _)) = self.split_key_value(&tag) else {
+            return check;
+        };
+        if check == TagCheck::Known {
+            return check;
+        }
+
+        // Suggestions keep the delimiter and the value.
+        let rest = &tag[key.len()..];
+        match vocabulary.check(key, self.unicode_form) {
+            TagCheck::Known => TagCheck::Known,
+            TagCheck::Alias { canonical } => TagCheck::Alias {
+                canonical: canonical + rest,
+            },
+            TagCheck::Unknown { closest } => TagCheck::Unknown {
+                closest: closest.map(|closest| closest + rest),
+            },
+        }
+    }
+
+    /// The tag followed by its ancestors in the vocabulary. Key/value tags also have the
+    /// ancestors of their key, `project=apollo` is about `work` when `project` is.
+    pub fn with_ancestors<'t>(&'t self, tag: &'t str) -> Vec<&'t str> {
+        let mut tags = vec![tag];
+        if let Some(vocabulary) = &self.vocabulary {
+            tags.extend(vocabulary.ancestors(tag, self.unicode_form));
+            if let Some((key, _)) = self.split_key_value(tag) {
+                for ancestor in vocabulary.ancestors(key, self.unicode_form) {
+                    if !tags.iter().any(|known| self.same_tag(known, ancestor)) {
+                        tags.push(ancestor);
+                    }
+                }
+            }
+        }
+        tags
+    }
+
     pub(crate) fn parse<'p>(&self, filename: &mut &'p str) -> Option<Tags<'p>> {
         for separator in &self.tag_main_separators {
             // We find the separator in the filename in order to make a reference to the path
@@ -43,37 +386,141 @@ impl TagConfiguration {
                 let separator_len = separator.len();
 
                 let (left, separator_and_left) = filename.split_at(separator_index);
-                let (mut separator, mut remainder) = separator_and_left.split_at(separator_len);
+                let (mut separator, remainder) = separator_and_left.split_at(separator_len);
 
                 *filename = left;
 
+                // Trailing spaces are kept with the separator to write the name back as it was.
                 if remainder.trim().is_empty() {
-                    return Some(Tags::SeparatorOnly(separator));
+                    return Some(Tags::SeparatorOnly(Cow::Borrowed(separator_and_left)));
                 }
 
                 let mut tags = Vec::new();
+                // Indices are relative to the whole remainder, so we keep track of where the
+                // current tag starts instead of slicing the remainder while iterating on it.
+                let mut tag_start = 0;
+
+                loop {
+                    let (raw, tag) = self.read_tag(&remainder[tag_start..]);
 
-                for (index, next_separator) in
-                    remainder.match_indices(&self.tag_between_separators[..])
-                {
                     tags.push(Tag {
-                        separator,
-                        tag: &remainder[..index],
+                        separator: Cow::Borrowed(separator),
+                        tag: Cow::Borrowed(tag),
+                        raw: Cow::Borrowed(raw),
                     });
 
-                    remainder = &remainder[(index + 1)..];
-                    separator = next_separator;
+                    // Every tag ends either at a between-separator or at the end of the name.
+                    tag_start += raw.len();
+                    match self.find_between_separator(&remainder[tag_start..]) {
+                        Some((0, next_separator)) => {
+                            tag_start += next_separator.len();
+                            separator = next_separator;
+                        }
+                        _ => break,
+                    }
                 }
 
-                tags.push(Tag {
-                    separator,
-                    tag: &remainder,
-                });
-
                 return Some(Tags::Tags(tags));
             }
         }
 
         None
     }
+
+    /// The tag the text starts with, as written and without its quotes.
+    fn read_tag<'t>(&self, text: &'t str) -> (&'t str, &'t str) {
+        self.find_quoted_tag(text).unwrap_or_else(|| {
+            let end = self
+                .find_between_separator(text)
+                .map_or(text.len(), |(index, _)| index);
+            (&text[..end], &text[..end])
+        })
+    }
+
+    /// Whether the tag, written as it is in `raw`, is read back the same whatever the tags
+    /// around it.
+    fn reads_back(&self, raw: &str, tag: &str) -> bool {
+        let main_separator = self.default_main_separator();
+        let separator = self.default_between_separator();
+
+        // The main separator is the last one of the name.
+        let text = format!("{separator}{raw}{separator}");
+        if !main_separator.is_empty()
+            && (text.contains(main_separator)
+                || format!("{main_separator}{raw}{separator}").rfind(main_separator) != Some(0))
+        {
+            return false;
+        }
+
+        // Opened and not closed, the quote would be closed in a tag after it.
+        if let Some(quotes) = self
+            .quotes
+            .as_ref()
+            .filter(|quotes| !quotes.close.is_empty())
+        {
+            let unclosed = raw
+                .strip_prefix(quotes.open.as_str())
+                .is_some_and(|inside| !inside.contains(quotes.close.as_str()));
+            if !quotes.open.is_empty() && unclosed {
+                return false;
+            }
+        }
+
+        let rest = match self.find_between_separator(&text) {
+            Some((0, found)) if found == separator => &text[separator.len()..],
+            None if separator.is_empty() => text.as_str(),
+            _ => return false,
+        };
+        if self.read_tag(rest) != (raw, tag) {
+            return false;
+        }
+
+        // Nor can the tag end like the beginning of a separator around the default one, or
+        // start like its end, it would merge with the tag next to it.
+        let straddles = |other: &str| {
+            other.match_indices(separator.as_str()).any(|(index, _)| {
+                let (before, after) = (&other[..index], &other[index + separator.len()..]);
+                !before.is_empty()
+                    && !after.is_empty()
+                    && (raw.ends_with(before) || raw.starts_with(after))
+            })
+        };
+        separator.is_empty()
+            || !self
+                .tag_between_separators
+                .iter()
+                .map(String::as_str)
+                .chain([main_separator])
+                .any(straddles)
+    }
+
+    /// The quoted tag the text starts with, as written and without its quotes. The closing quote
+    /// must be followed by a between-separator or end the name, the text is not quoted otherwise.
+    fn find_quoted_tag<'t>(&self, text: &'t str) -> Option<(&'t str, &'t str)> {
+        let quotes = self.quotes.as_ref()?;
+        if quotes.open.is_empty() || quotes.close.is_empty() {
+            return None;
+        }
+
+        let inside = text.strip_prefix(quotes.open.as_str())?;
+        let close = inside.find(quotes.close.as_str())?;
+        let end = quotes.open.len() + close + quotes.close.len();
+
+        let after = &text[end..];
+        let separated =
+            after.is_empty() || matches!(self.find_between_separator(after), Some((0, _)));
+        separated.then(|| (&text[..end], &inside[..close]))
+    }
+
+    /// The first between-separator of the text and its byte index.
+    fn find_between_separator<'t>(&self, text: &'t str) -> Option<(usize, &'t str)> {
+        self.tag_between_separators
+            .iter()
+            .filter(|separator| !separator.is_empty())
+            .filter_map(|separator| {
+                let index = text.find(separator.as_str())?;
+                Some((index, &text[index..index + separator.len()]))
+            })
+            .min_by_key(|(index, separator)| (*index, Reverse(separator.len())))
+    }
 }
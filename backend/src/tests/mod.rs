@@ -1,31 +1,75 @@
 use crate::{
     configuration::{TimestampConfiguration, TimestampVariantConfiguration},
-    StructuredName, StructuredNameConfiguration, StructuredPath, Tag, TagConfiguration, Tags,
-    Timestamp, TimestampValue,
+    CollisionStrategy, DayMonthOrder, Extractors, FuzzyDates, Journal, JournalError, Query,
+    QueryError, RenameError, RenameOutcome, RenameTransaction, Renamer, StructuredName,
+    StructuredNameConfiguration, StructuredPath, Tag, TagCheck, TagConfiguration, TagError,
+    TagExpression, TagOrder, TagQuotes, TagReport, TagVocabulary, Tags, Timestamp, TimestampKind,
+    TimestampSource, TimestampValue, TransactionError, UndoSelection, UnicodeForm, WalkOptions,
+    WalkTargets,
 };
 use camino::{Utf8Path, Utf8PathBuf};
-use chrono::NaiveDate;
+use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use pretty_assertions::assert_eq;
+use proptest::prelude::*;
+use std::{fs, sync::LazyLock};
 
 /// Allow us to get valid OS-specific paths to not fail on inconsistent separators.
 pub fn create_path() -> Utf8PathBuf {
     Utf8PathBuf::from("some").join("folder")
 }
 
+/// Built once, its import patterns are slow to compile.
+static DEFAULT_CONFIGURATION: LazyLock<StructuredNameConfiguration> =
+    LazyLock::new(StructuredNameConfiguration::default);
+
 /// Configuration should not be super exotic.
 pub fn create_configuration() -> StructuredNameConfiguration {
     StructuredNameConfiguration {
         timestamp_configuration: TimestampConfiguration {
             date: TimestampVariantConfiguration {
                 formats: vec!["%Y-%m-%d".to_string(), "%Y_%m_%d".to_string()],
+                fuzzy: None,
             },
             date_time: TimestampVariantConfiguration {
                 formats: vec!["%Y-%m-%d %Hh%M".to_string()],
+                fuzzy: None,
+            },
+            offset_date_time: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m-%d %Hh%M%z".to_string()],
+                fuzzy: None,
+            },
+            time: TimestampVariantConfiguration {
+                formats: vec!["%Hh%M".to_string()],
+                fuzzy: None,
+            },
+            date_range: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m-%d to %Y-%m-%d".to_string()],
+                fuzzy: None,
+            },
+            week: TimestampVariantConfiguration {
+                formats: vec!["%G-W%V".to_string()],
+                fuzzy: None,
+            },
+            month: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m".to_string()],
+                fuzzy: None,
+            },
+            quarter: TimestampVariantConfiguration {
+                formats: vec!["%Y Q%q".to_string()],
+                fuzzy: None,
             },
+            default_timezone: None,
+            import: vec![],
         },
         tag_configuration: TagConfiguration {
             tag_main_separators: vec![" -- ".to_string()],
-            tag_between_separators: vec![' '],
+            tag_between_separators: vec![" ".to_string()],
+            vocabulary: None,
+            tag_order: TagOrder::Keep,
+            unicode_form: None,
+            quotes: None,
+            key_value_delimiters: vec![],
         },
     }
 }
@@ -43,10 +87,29 @@ pub fn check(path: StructuredPath, normalised_path: &Utf8Path) {
 
     // The configuration allow multiple date formats and there is a way to
     // keep everything tidy.
-    result.normalise();
+    result.normalise().unwrap();
     assert_eq!(result.to_string().as_str(), normalised_path.as_str());
 }
 
+/// Edits go through the same `Display` as parsed names, so the edited name must parse back
+/// into the very same structure.
+pub fn check_edit(path: &Utf8Path, edit: impl FnOnce(&mut StructuredName), edited_path: &Utf8Path) {
+    let config = create_configuration();
+    let mut result = StructuredPath::parse_from(path, &config);
+
+    edit(&mut result.name);
+    assert_eq!(result.to_string().as_str(), edited_path.as_str());
+
+    // Parsed timestamps remember their text, edited ones do not.
+    let mut reparsed = StructuredPath::parse_from(edited_path, &config);
+    for name in [&mut reparsed.name, &mut result.name] {
+        if let Some(timestamp) = name.timestamp.as_mut() {
+            timestamp.original = None;
+        }
+    }
+    assert_eq!(reparsed.name, result.name);
+}
+
 #[test]
 pub fn parse_path_without_date() {
     let config = create_configuration();
@@ -58,7 +121,7 @@ pub fn parse_path_without_date() {
             name: StructuredName {
                 configuration: &config,
                 timestamp: None,
-                filename: Some("Some filename"),
+                filename: Some("Some filename".into()),
                 tags: None,
                 extension: Some("txt"),
             },
@@ -81,8 +144,9 @@ pub fn parse_path_with_date() {
                     configuration: &config.timestamp_configuration.date,
                     format_index: 0,
                     value: TimestampValue::Date(NaiveDate::from_ymd_opt(2022, 10, 27).unwrap()),
+                    original: Some("2022-10-27"),
                 }),
-                filename: Some("-Some-filename"),
+                filename: Some("-Some-filename".into()),
                 tags: None,
                 extension: Some("pdf"),
             },
@@ -110,8 +174,9 @@ pub fn parse_path_with_date_time() {
                             .and_hms_opt(15, 35, 0)
                             .unwrap(),
                     ),
+                    original: Some("2022-10-27 15h35"),
                 }),
-                filename: Some(" Some filename"),
+                filename: Some(" Some filename".into()),
                 tags: None,
                 extension: Some("pdf"),
             },
@@ -135,8 +200,9 @@ pub fn parse_path_with_secondary_date() {
                     configuration: &config.timestamp_configuration.date,
                     format_index: 1,
                     value: TimestampValue::Date(NaiveDate::from_ymd_opt(2022, 10, 27).unwrap()),
+                    original: Some("2022_10_27"),
                 }),
-                filename: Some(" Some filename"),
+                filename: Some(" Some filename".into()),
                 tags: None,
                 extension: Some("pdf"),
             },
@@ -145,6 +211,320 @@ pub fn parse_path_with_secondary_date() {
     );
 }
 
+#[test]
+pub fn parse_path_with_time() {
+    let config = create_configuration();
+    let path = create_path().join("09h05 Voice memo.m4a");
+
+    check(
+        StructuredPath {
+            original_path: &path,
+            name: StructuredName {
+                configuration: &config,
+                timestamp: Some(Timestamp {
+                    configuration: &config.timestamp_configuration.time,
+                    format_index: 0,
+                    value: TimestampValue::Time(NaiveTime::from_hms_opt(9, 5, 0).unwrap()),
+                    original: Some("09h05"),
+                }),
+                filename: Some(" Voice memo".into()),
+                tags: None,
+                extension: Some("m4a"),
+            },
+        },
+        &path,
+    );
+
+    // Not written as the format would.
+    let path = create_path().join("9h5 Voice memo.m4a");
+    let name = StructuredName::parse_from(&path, &config);
+    assert_eq!(name.timestamp, None);
+
+    // A date in a time format cannot be rendered from a time alone.
+    let mut config = config;
+    config.timestamp_configuration.time.formats = vec!["%H.%M %Y".to_string()];
+    let path = create_path().join("10.30 2022 memo.txt");
+    let name = StructuredName::parse_from(&path, &config);
+    assert_eq!(name.timestamp, None);
+    assert_eq!(name.to_string(), "10.30 2022 memo.txt");
+}
+
+#[test]
+pub fn parse_periods() {
+    let config = create_configuration();
+    let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+    let parse = |name: &str| {
+        let path = Utf8PathBuf::from(name);
+        let name = StructuredName::parse_from(&path, &config);
+        // Every period is written back as it was.
+        assert_eq!(name.to_string(), path.as_str());
+        name.timestamp
+            .map(|timestamp| (timestamp.value, name.filename.unwrap().into_owned()))
+    };
+
+    assert_eq!(
+        parse("2022-10-27 to 2022-11-03 Trip"),
+        Some((
+            TimestampValue::DateRange(date(10, 27), date(11, 3)),
+            " Trip".to_string()
+        ))
+    );
+    assert_eq!(
+        parse("2022-W43 Report.pdf"),
+        Some((TimestampValue::Week(date(10, 24)), " Report".to_string()))
+    );
+    assert_eq!(
+        parse("2022-10 Statement.pdf"),
+        Some((TimestampValue::Month(date(10, 1)), " Statement".to_string()))
+    );
+    assert_eq!(
+        parse("2022 Q4 Budget.ods"),
+        Some((TimestampValue::Quarter(date(10, 1)), " Budget".to_string()))
+    );
+    // The end comes before the start, only the first date is read.
+    assert_eq!(
+        parse("2022-11-03 to 2022-10-27 Trip").map(|(value, _)| value),
+        Some(TimestampValue::Date(date(11, 3)))
+    );
+    assert_eq!(parse("2022 Q5 Budget.ods"), None);
+    // Ranges are only read when written exactly as their format renders them.
+    assert_eq!(
+        parse("2022-10-27 to  2022-11-03 Trip").map(|(value, _)| value),
+        Some(TimestampValue::Date(date(10, 27)))
+    );
+
+    // Normalising a date followed by what looks like a range does not make one.
+    let mut config = create_configuration();
+    config.timestamp_configuration.date_range.formats = vec!["%Y-%m-%d--%Y-%m-%d".to_string()];
+    let path = Utf8PathBuf::from("2022_10_27-- 2022-10-27 Trip");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.normalise().unwrap();
+    let normalised = Utf8PathBuf::from(name.to_string());
+    assert_eq!(normalised, "2022-10-27-- 2022-10-27 Trip");
+    let mut name = StructuredName::parse_from(&normalised, &config);
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), normalised.as_str());
+
+    assert_eq!(
+        serde_json::to_string(&[
+            TimestampValue::DateRange(date(10, 27), date(11, 3)),
+            TimestampValue::Week(date(10, 24)),
+            TimestampValue::Month(date(10, 1)),
+            TimestampValue::Quarter(date(10, 1)),
+        ])
+        .unwrap(),
+        r#"[["2022-10-27","2022-11-03"],"2022-W43","2022-10","2022-Q4"]"#
+    );
+}
+
+#[test]
+pub fn keep_timestamps_as_written() {
+    let config = create_configuration();
+    let path = create_path().join("2022-1-5 note -- tag.txt");
+    let mut result = StructuredPath::parse_from(&path, &config);
+    assert_eq!(result.to_string(), path.as_str());
+
+    // Other edits leave the timestamp alone.
+    result.name.add_tag("other").unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("2022-1-5 note -- tag other.txt")
+    );
+
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("2022-01-05 note -- tag other.txt")
+    );
+}
+
+#[test]
+pub fn display_timestamps_without_usable_format() {
+    let mut config = create_configuration();
+    let value = TimestampValue::Date(NaiveDate::from_ymd_opt(2022, 10, 27).unwrap());
+
+    // A time cannot be written with a date, the next format is used.
+    config.timestamp_configuration.date.formats = vec!["%Hh".to_string(), "%Y_%m_%d".to_string()];
+    let timestamp = config.timestamp_configuration.new_timestamp(value);
+    assert_eq!(timestamp.to_string(), "2022_10_27");
+
+    config.timestamp_configuration.date.formats = vec![];
+    let timestamp = config.timestamp_configuration.new_timestamp(value);
+    assert_eq!(timestamp.to_string(), "2022-10-27");
+}
+
+#[test]
+pub fn parse_fuzzy_dates() {
+    let mut config = create_configuration();
+    config.timestamp_configuration.date.fuzzy = Some(FuzzyDates::default());
+    let date = |config: &StructuredNameConfiguration, name: &str| {
+        let path = Utf8PathBuf::from(name);
+        let name = StructuredName::parse_from(&path, config);
+        name.timestamp
+            .map(|timestamp| (timestamp.value, name.filename.unwrap().into_owned()))
+    };
+    let october = |day| TimestampValue::Date(NaiveDate::from_ymd_opt(2022, 10, day).unwrap());
+
+    assert_eq!(
+        date(&config, "27 octobre 2022 Réunion.pdf"),
+        Some((october(27), " Réunion".to_string()))
+    );
+    assert_eq!(
+        date(&config, "1er Octobre 22.pdf"),
+        Some((october(1), String::new()))
+    );
+    assert_eq!(
+        date(&config, "Oct 27, 2022 Minutes.pdf"),
+        Some((october(27), " Minutes".to_string()))
+    );
+    assert_eq!(
+        date(&config, "27-déc-2022.pdf").map(|(value, _)| value),
+        Some(TimestampValue::Date(
+            NaiveDate::from_ymd_opt(2022, 12, 27).unwrap()
+        ))
+    );
+    // Numbers above 12 can only be days, the others follow the configured order.
+    assert_eq!(
+        date(&config, "10.27.22 Notes.txt").map(|(value, _)| value),
+        Some(october(27))
+    );
+    assert_eq!(
+        date(&config, "05.10.22 Notes.txt").map(|(value, _)| value),
+        Some(october(5))
+    );
+    config.timestamp_configuration.date.fuzzy = Some(FuzzyDates {
+        order: DayMonthOrder::MonthFirst,
+        ..FuzzyDates::default()
+    });
+    assert_eq!(
+        date(&config, "10.05.22 Notes.txt").map(|(value, _)| value),
+        Some(october(5))
+    );
+    assert_eq!(
+        date(&config, "10.05.69 Notes.txt").map(|(value, _)| value),
+        Some(TimestampValue::Date(
+            NaiveDate::from_ymd_opt(2069, 10, 5).unwrap()
+        ))
+    );
+
+    // Times read exactly by a time format are not dates.
+    config
+        .timestamp_configuration
+        .time
+        .formats
+        .push("%H.%M.%S".to_string());
+    let time = |hour, minute, second| {
+        Some(TimestampValue::Time(
+            NaiveTime::from_hms_opt(hour, minute, second).unwrap(),
+        ))
+    };
+    assert_eq!(
+        date(&config, "09.30.00 Meeting.m4a").map(|(value, _)| value),
+        time(9, 30, 0)
+    );
+    assert_eq!(
+        date(&config, "11.05.42 Standup.m4a").map(|(value, _)| value),
+        time(11, 5, 42)
+    );
+
+    // Version numbers and names that only look like dates.
+    assert_eq!(date(&config, "1.2.3 Release notes.txt"), None);
+    assert_eq!(date(&config, "May the force.txt"), None);
+    assert_eq!(date(&config, "27 octobre.txt"), None);
+    assert_eq!(date(&config, "30 February 2022.txt"), None);
+
+    // Written back as it was, the main format is used once normalised.
+    config.timestamp_configuration.date.fuzzy = Some(FuzzyDates::default());
+    let path = Utf8PathBuf::from("27 octobre 2022 Réunion.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    assert_eq!(name.to_string(), "27 octobre 2022 Réunion.pdf");
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), "2022-10-27 Réunion.pdf");
+}
+
+#[test]
+pub fn import_timestamps() {
+    let config = StructuredNameConfiguration::default();
+    let import = |name: &str| {
+        let path = Utf8PathBuf::from(name);
+        let mut name = StructuredName::parse_from(&path, &config);
+        // Parsing alone keeps the name as it is.
+        assert_eq!(name.to_string(), path.as_str());
+        name.import_timestamp();
+        name.to_string()
+    };
+
+    assert_eq!(import("IMG_20221027_153500.jpg"), "2022-10-27T15.35.00.jpg");
+    assert_eq!(
+        import("Screenshot from 2022-10-27 15-35-00.png"),
+        "2022-10-27T15.35.00.png"
+    );
+    assert_eq!(
+        import("PXL_20221027_153500123.PORTRAIT.jpg"),
+        "2022-10-27T15.35.00 PORTRAIT.jpg"
+    );
+    assert_eq!(
+        import("IMG-20221027-WA0001 -- beach.jpg"),
+        "2022-10-27 WA0001 -- beach.jpg"
+    );
+    // Names starting with a timestamp are left alone.
+    assert_eq!(
+        import("2022-10-27 IMG_20221027_153500.jpg"),
+        "2022-10-27 IMG_20221027_153500.jpg"
+    );
+
+    let config: StructuredNameConfiguration = toml::from_str(
+        r#"
+        [[timestamp.import]]
+        pattern = 'scan_(?P<timestamp>\d{2}\.\d{2}\.\d{4})'
+        format = "%d.%m.%Y"
+        "#,
+    )
+    .unwrap();
+    let path = Utf8PathBuf::from("Letter scan_27.10.2022_bank.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), "2022-10-27 Letter bank.pdf");
+
+    let missing_group = toml::from_str::<StructuredNameConfiguration>(
+        r#"
+        [[timestamp.import]]
+        pattern = '^IMG_\d{8}'
+        format = "%Y%m%d"
+        "#,
+    );
+    assert!(missing_group.is_err());
+}
+
+#[test]
+pub fn parse_path_with_offset() {
+    let config = create_configuration();
+    let path = create_path().join("2022-10-27 15h35+0200 Meeting.txt");
+    let date_time = FixedOffset::east_opt(2 * 3600)
+        .unwrap()
+        .with_ymd_and_hms(2022, 10, 27, 15, 35, 0)
+        .unwrap();
+
+    check(
+        StructuredPath {
+            original_path: &path,
+            name: StructuredName {
+                configuration: &config,
+                timestamp: Some(Timestamp {
+                    configuration: &config.timestamp_configuration.offset_date_time,
+                    format_index: 0,
+                    value: TimestampValue::OffsetDateTime(date_time),
+                    original: Some("2022-10-27 15h35+0200"),
+                }),
+                filename: Some(" Meeting".into()),
+                tags: None,
+                extension: Some("txt"),
+            },
+        },
+        &path,
+    );
+}
+
 #[test]
 pub fn parse_path_with_secondary_timestamp_and_tags() {
     let config = create_configuration();
@@ -160,16 +540,19 @@ pub fn parse_path_with_secondary_timestamp_and_tags() {
                     configuration: &config.timestamp_configuration.date,
                     format_index: 1,
                     value: TimestampValue::Date(NaiveDate::from_ymd_opt(2022, 10, 27).unwrap()),
+                    original: Some("2022_10_27"),
                 }),
-                filename: Some(" Some filename"),
+                filename: Some(" Some filename".into()),
                 tags: Some(Tags::Tags(vec![
                     Tag {
-                        separator: " -- ",
-                        tag: "tag",
+                        separator: " -- ".into(),
+                        tag: "tag".into(),
+                        raw: "tag".into(),
                     },
                     Tag {
-                        separator: " ",
-                        tag: "test",
+                        separator: " ".into(),
+                        tag: "test".into(),
+                        raw: "test".into(),
                     },
                 ])),
                 extension: Some("pdf"),
@@ -178,3 +561,1442 @@ pub fn parse_path_with_secondary_timestamp_and_tags() {
         &normalised_path,
     );
 }
+
+#[test]
+pub fn parse_path_with_many_tags() {
+    let config = create_configuration();
+    let path = create_path().join("Some filename -- first second third.pdf");
+
+    check(
+        StructuredPath {
+            original_path: &path,
+            name: StructuredName {
+                configuration: &config,
+                timestamp: None,
+                filename: Some("Some filename".into()),
+                tags: Some(Tags::Tags(vec![
+                    Tag {
+                        separator: " -- ".into(),
+                        tag: "first".into(),
+                        raw: "first".into(),
+                    },
+                    Tag {
+                        separator: " ".into(),
+                        tag: "second".into(),
+                        raw: "second".into(),
+                    },
+                    Tag {
+                        separator: " ".into(),
+                        tag: "third".into(),
+                        raw: "third".into(),
+                    },
+                ])),
+                extension: Some("pdf"),
+            },
+        },
+        &path,
+    );
+}
+
+#[test]
+pub fn edit_add_tags() {
+    check_edit(
+        &create_path().join("Some filename.txt"),
+        |name| {
+            name.add_tag("first").unwrap();
+            name.add_tag("second").unwrap();
+            name.add_tag("first").unwrap();
+        },
+        &create_path().join("Some filename -- first second.txt"),
+    );
+}
+
+#[test]
+pub fn edit_add_tag_after_separator_only() {
+    check_edit(
+        &create_path().join("Some filename -- .txt"),
+        |name| name.add_tag("tag").unwrap(),
+        &create_path().join("Some filename -- tag.txt"),
+    );
+
+    // Blanks after the separator do not become an empty tag.
+    let config = create_configuration();
+    let path = create_path().join("Some filename --  .txt");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.add_tag("tag").unwrap();
+    assert_eq!(name.to_string(), "Some filename -- tag.txt");
+    let path = Utf8PathBuf::from(name.to_string());
+    let name = StructuredName::parse_from(&path, &config);
+    assert_eq!(
+        name.tags.iter().flat_map(Tags::iter).collect::<Vec<_>>(),
+        vec!["tag"]
+    );
+}
+
+#[test]
+pub fn edit_remove_tags() {
+    check_edit(
+        &create_path().join("Some filename -- first second third.txt"),
+        |name| {
+            assert!(name.remove_tag("first"));
+            assert!(name.remove_tag("third"));
+            assert!(!name.remove_tag("missing"));
+        },
+        &create_path().join("Some filename -- second.txt"),
+    );
+
+    check_edit(
+        &create_path().join("Some filename -- tag.txt"),
+        |name| {
+            assert!(name.remove_tag("tag"));
+        },
+        &create_path().join("Some filename.txt"),
+    );
+}
+
+#[test]
+pub fn edit_replace_tags() {
+    check_edit(
+        &create_path().join("Some filename -- first inv second.txt"),
+        |name| {
+            assert!(name.replace_tags(&["inv"], "invoice").unwrap());
+            assert!(!name.replace_tags(&["missing"], "other").unwrap());
+        },
+        &create_path().join("Some filename -- first invoice second.txt"),
+    );
+
+    check_edit(
+        &create_path().join("Some filename -- a x b.txt"),
+        |name| assert!(name.replace_tags(&["a", "b"], "c").unwrap()),
+        &create_path().join("Some filename -- c x.txt"),
+    );
+
+    check_edit(
+        &create_path().join("Some filename -- a c b.txt"),
+        |name| assert!(name.replace_tags(&["a", "b"], "c").unwrap()),
+        &create_path().join("Some filename -- c.txt"),
+    );
+}
+
+#[test]
+pub fn edit_set_timestamp() {
+    let date = NaiveDate::from_ymd_opt(2022, 10, 27).unwrap();
+
+    check_edit(
+        &create_path().join("Some filename.txt"),
+        |name| name.set_timestamp(TimestampValue::Date(date)),
+        &create_path().join("2022-10-27 Some filename.txt"),
+    );
+
+    check_edit(
+        &create_path().join("2021_01_02 Some filename.txt"),
+        |name| {
+            name.set_timestamp(TimestampValue::DateTime(
+                date.and_hms_opt(15, 35, 0).unwrap(),
+            ))
+        },
+        &create_path().join("2022-10-27 15h35 Some filename.txt"),
+    );
+
+    check_edit(
+        &create_path().join("Some filename.txt"),
+        |name| {
+            name.set_timestamp(TimestampValue::Time(
+                NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            ))
+        },
+        &create_path().join("07h00 Some filename.txt"),
+    );
+}
+
+#[test]
+pub fn edit_prefix_and_suffix() {
+    check_edit(
+        &create_path().join("2022-10-27 Some filename -- tag.txt"),
+        |name| {
+            name.push_prefix("Draft ");
+            name.push_suffix(" v2");
+        },
+        &create_path().join("2022-10-27 Draft Some filename v2 -- tag.txt"),
+    );
+
+    check_edit(
+        &create_path().join("Some filename.txt"),
+        |name| name.push_prefix("Draft "),
+        &create_path().join("Draft Some filename.txt"),
+    );
+
+    // A name with a timestamp alone gets a space before its prefix.
+    check_edit(
+        &create_path().join("2022-10-27.txt"),
+        |name| name.push_prefix("pre"),
+        &create_path().join("2022-10-27 pre.txt"),
+    );
+
+    check_edit(
+        &create_path().join("2022-10-27 -- tag.txt"),
+        |name| name.push_prefix("pre"),
+        &create_path().join("2022-10-27 pre -- tag.txt"),
+    );
+}
+
+#[test]
+pub fn normalise_tags() {
+    let mut config = create_configuration();
+    config.tag_configuration.tag_main_separators = vec![" -- ".to_string(), " - ".to_string()];
+
+    let path = create_path().join("2022_10_27 Some filename - first second first.txt");
+    let mut result = StructuredPath::parse_from(&path, &config);
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path()
+            .join("2022-10-27 Some filename -- first second.txt")
+            .to_string()
+    );
+
+    config.tag_configuration.tag_between_separators = vec![" ".to_string(), "_".to_string()];
+    let path = create_path().join("Some filename -- b_a  c.txt");
+    let mut result = StructuredPath::parse_from(&path, &config);
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("Some filename -- b a c.txt").to_string()
+    );
+
+    config.tag_configuration.tag_order = TagOrder::Alphabetical;
+    let mut result = StructuredPath::parse_from(&path, &config);
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("Some filename -- a b c.txt").to_string()
+    );
+
+    config.tag_configuration.tag_order = TagOrder::Vocabulary;
+    config.tag_configuration.vocabulary = Some(TagVocabulary {
+        allowed: vec!["c".to_string(), "b".to_string()],
+        ..Default::default()
+    });
+    let mut result = StructuredPath::parse_from(&path, &config);
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("Some filename -- c b a.txt").to_string()
+    );
+
+    let path = create_path().join("Some filename - .txt");
+    let mut result = StructuredPath::parse_from(&path, &config);
+    result.normalise().unwrap();
+    assert_eq!(
+        result.to_string(),
+        create_path().join("Some filename -- .txt").to_string()
+    );
+}
+
+#[test]
+pub fn convert_timezones() {
+    let mut config = create_configuration();
+    config.timestamp_configuration.default_timezone = Some(Tz::Europe__Paris);
+
+    let convert = |name: &str| {
+        let path = create_path().join(name);
+        let mut result = StructuredPath::parse_from(&path, &config);
+        let converted = result.name.convert_timezone(Tz::UTC);
+        (converted, result.to_string())
+    };
+
+    assert_eq!(
+        convert("2022-10-27 15h35 Meeting.txt"),
+        (
+            true,
+            create_path()
+                .join("2022-10-27 13h35+0000 Meeting.txt")
+                .to_string()
+        )
+    );
+    assert_eq!(
+        convert("2022-10-27 22h35-0400 Meeting.txt"),
+        (
+            true,
+            create_path()
+                .join("2022-10-28 02h35+0000 Meeting.txt")
+                .to_string()
+        )
+    );
+    assert_eq!(
+        convert("2022-10-27 Meeting.txt"),
+        (
+            false,
+            create_path().join("2022-10-27 Meeting.txt").to_string()
+        )
+    );
+}
+
+#[test]
+pub fn timestamp_from_metadata() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("file.txt");
+    let modified = Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap();
+    fs::File::create(&path)
+        .unwrap()
+        .set_modified(modified.into())
+        .unwrap();
+    let metadata = fs::metadata(&path).unwrap();
+
+    let mut config = create_configuration();
+    config.timestamp_configuration.default_timezone = Some(Tz::Europe__Paris);
+    let timestamps = &config.timestamp_configuration;
+
+    let timestamp = timestamps
+        .timestamp_from_metadata(
+            &metadata,
+            TimestampSource::Modified,
+            TimestampKind::DateTime,
+        )
+        .unwrap();
+    assert_eq!(timestamp.to_string(), "2021-03-04 06h06");
+
+    // The file was created after the modification time we gave it.
+    let timestamp = timestamps
+        .timestamp_from_metadata(&metadata, TimestampSource::Oldest, TimestampKind::Date)
+        .unwrap();
+    assert_eq!(timestamp.to_string(), "2021-03-04");
+}
+
+#[test]
+pub fn extract_embedded_timestamps() {
+    let directory = tempfile::tempdir().unwrap();
+    let directory = Utf8Path::from_path(directory.path()).unwrap();
+    let extractors = Extractors::default();
+
+    // A video with only a file type and a version 0 movie header.
+    let seconds = 1_666_877_700u32 + 2_082_844_800;
+    let mut mvhd = vec![0, 0, 0, 28];
+    mvhd.extend(b"mvhd");
+    mvhd.extend([0; 4]);
+    mvhd.extend(seconds.to_be_bytes());
+    mvhd.extend([0; 12]);
+    let mut video = vec![0, 0, 0, 16];
+    video.extend(b"ftypisom\0\0\0\0");
+    video.extend((mvhd.len() as u32 + 8).to_be_bytes());
+    video.extend(b"moov");
+    video.extend(mvhd);
+    fs::write(directory.join("video.MP4"), video).unwrap();
+
+    fs::write(
+        directory.join("document.pdf"),
+        "%PDF-1.4\n1 0 obj\n<< /Title (Minutes) /CreationDate (D:20221027153500+02'00') >>\nendobj\n",
+    )
+    .unwrap();
+    fs::write(
+        directory.join("drawing.svg"),
+        r#"<svg><metadata><rdf:Description xmp:CreateDate="2022-10-27"/></metadata></svg>"#,
+    )
+    .unwrap();
+    fs::write(directory.join("notes.txt"), "CreationDate 2022-10-27").unwrap();
+
+    assert_eq!(
+        extractors.extract(&directory.join("video.MP4")).unwrap(),
+        Some(TimestampValue::OffsetDateTime(
+            Utc.with_ymd_and_hms(2022, 10, 27, 13, 35, 0)
+                .unwrap()
+                .fixed_offset()
+        ))
+    );
+    assert_eq!(
+        extractors.extract(&directory.join("notes.txt")).unwrap(),
+        None
+    );
+
+    let mut config = create_configuration();
+    config.timestamp_configuration.default_timezone = Some(Tz::Europe__Paris);
+    let timestamps = &config.timestamp_configuration;
+    let timestamp = |name: &str, kind| {
+        timestamps
+            .timestamp_for(
+                &directory.join(name),
+                TimestampSource::Embedded,
+                kind,
+                &extractors,
+            )
+            .unwrap()
+            .map(|timestamp| timestamp.to_string())
+    };
+
+    assert_eq!(
+        timestamp("video.MP4", TimestampKind::DateTime).as_deref(),
+        Some("2022-10-27 15h35")
+    );
+    assert_eq!(
+        timestamp("document.pdf", TimestampKind::DateTime).as_deref(),
+        Some("2022-10-27 15h35")
+    );
+    // Only the date is known, no time is made up.
+    assert_eq!(
+        timestamp("drawing.svg", TimestampKind::DateTime).as_deref(),
+        Some("2022-10-27")
+    );
+    assert_eq!(timestamp("drawing.svg", TimestampKind::Time), None);
+    assert_eq!(timestamp("notes.txt", TimestampKind::Date), None);
+
+    // Box sizes going past the end of the file, or overflowing, stop the search.
+    for size in [u64::MAX, u64::MAX - 7, 1 << 40] {
+        let mut video = vec![0, 0, 0, 1];
+        video.extend(b"free");
+        video.extend(size.to_be_bytes());
+        video.extend([0, 0, 0, 8]);
+        video.extend(b"moov");
+        fs::write(directory.join("broken.mp4"), &video).unwrap();
+        assert_eq!(
+            extractors.extract(&directory.join("broken.mp4")).unwrap(),
+            None
+        );
+    }
+
+    // Movie headers too short for their creation time, or cut by the end of the file.
+    for (size, content) in [(12, vec![0; 4]), (20, vec![1, 0, 0, 0, 0, 0])] {
+        let mut video = (size + 8u32).to_be_bytes().to_vec();
+        video.extend(b"moov");
+        video.extend(size.to_be_bytes());
+        video.extend(b"mvhd");
+        video.extend(content);
+        fs::write(directory.join("broken.mp4"), &video).unwrap();
+        assert_eq!(
+            extractors.extract(&directory.join("broken.mp4")).unwrap(),
+            None
+        );
+    }
+
+    // Invalid EXIF data does not hide the XMP packet.
+    fs::write(
+        directory.join("broken.jpg"),
+        b"\xff\xd8\xff\xe1\x00\x08Exif\x00\x00<x xmp:CreateDate=\"2022-10-27\"/>",
+    )
+    .unwrap();
+    assert_eq!(
+        timestamp("broken.jpg", TimestampKind::Date).as_deref(),
+        Some("2022-10-27")
+    );
+
+    // A malformed offset leaves the date and time as they are.
+    fs::write(
+        directory.join("offset.pdf"),
+        "%PDF-1.4\n<< /CreationDate (D:20221027153500+999999999'00') >>\n",
+    )
+    .unwrap();
+    assert_eq!(
+        extractors.extract(&directory.join("offset.pdf")).unwrap(),
+        Some(TimestampValue::DateTime(
+            NaiveDate::from_ymd_opt(2022, 10, 27)
+                .unwrap()
+                .and_hms_opt(15, 35, 0)
+                .unwrap()
+        ))
+    );
+
+    // Big documents are only read at their start and their end.
+    let mut document = b"%PDF-1.4\n".to_vec();
+    document.resize(3 << 20, b' ');
+    document.extend(b"<< /CreationDate (D:20221027) >>\n");
+    fs::write(directory.join("big.pdf"), document).unwrap();
+    assert_eq!(
+        timestamp("big.pdf", TimestampKind::Date).as_deref(),
+        Some("2022-10-27")
+    );
+}
+
+#[test]
+pub fn load_configuration_without_files() {
+    assert_eq!(
+        StructuredNameConfiguration::load_from_files(&[]).unwrap(),
+        StructuredNameConfiguration::default()
+    );
+}
+
+#[test]
+pub fn load_layered_configuration() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().canonicalize().unwrap()).unwrap();
+    let nested = root.join("archive").join("2022");
+    fs::create_dir_all(&nested).unwrap();
+
+    fs::write(
+        root.join(".tagger.toml"),
+        "[timestamp]\ndate.formats = [\"%Y_%m_%d\"]\n[tags]\nmain_separators = [\" -- \"]\n",
+    )
+    .unwrap();
+    fs::write(
+        nested.join(".tagger.toml"),
+        "[tags]\nmain_separators = [\" - \"]\n",
+    )
+    .unwrap();
+
+    let target = nested.join("2022_10_27 Some filename - tag.pdf");
+    let files = StructuredNameConfiguration::directory_configuration_paths(&target);
+    assert_eq!(
+        files,
+        vec![root.join(".tagger.toml"), nested.join(".tagger.toml")]
+    );
+
+    let mut expected = StructuredNameConfiguration::default();
+    expected.timestamp_configuration.date.formats = vec!["%Y_%m_%d".to_string()];
+    expected.tag_configuration.tag_main_separators = vec![" - ".to_string()];
+    assert_eq!(
+        StructuredNameConfiguration::load_from_files(&files).unwrap(),
+        expected
+    );
+}
+
+#[test]
+pub fn undo_renames_from_journal() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().canonicalize().unwrap()).unwrap();
+    let journal_path = root.join("journal.jsonl");
+
+    // Two sessions renaming files one after the other.
+    let rename = |journal: &Journal, from: &str, to: &str| {
+        fs::rename(root.join(from), root.join(to)).unwrap();
+        journal.record(&root.join(from), &root.join(to)).unwrap();
+    };
+    for name in ["a", "b", "c"] {
+        fs::write(root.join(name), name).unwrap();
+    }
+    let first = Journal::with_session(journal_path.clone(), "first".to_string());
+    rename(&first, "a", "a -- tag");
+    rename(&first, "b", "b -- tag");
+    let second = Journal::with_session(journal_path.clone(), "second".to_string());
+    rename(&second, "c", "c -- tag");
+    rename(&second, "a -- tag", "a -- other");
+
+    let undone = second.undo(&UndoSelection::Last(1)).unwrap();
+    assert_eq!(undone.len(), 1);
+    assert!(root.join("a -- tag").exists());
+
+    let undone = second
+        .undo(&UndoSelection::Session("first".to_string()))
+        .unwrap();
+    assert_eq!(undone.len(), 2);
+    assert_eq!(undone[0].original_path, root.join("b"));
+    assert!(root.join("a").exists() && root.join("b").exists());
+
+    let undone = second.undo(&UndoSelection::LastSession).unwrap();
+    assert_eq!(undone.len(), 1);
+    assert!(root.join("c").exists());
+    assert!(second.entries().unwrap().is_empty());
+
+    // The renames undone before a failure are reported with it.
+    let third = Journal::with_session(journal_path.clone(), "third".to_string());
+    rename(&third, "a", "a -- tag");
+    rename(&third, "b", "b -- tag");
+    fs::remove_file(root.join("a -- tag")).unwrap();
+    match third.undo(&UndoSelection::LastSession) {
+        Err(JournalError::PartialUndo { undone, source }) => {
+            assert_eq!(undone.len(), 1);
+            assert_eq!(undone[0].original_path, root.join("b"));
+            assert!(matches!(*source, JournalError::Undo { .. }));
+        }
+        result => panic!("unexpected {result:?}"),
+    }
+    assert_eq!(third.entries().unwrap().len(), 1);
+}
+
+#[test]
+pub fn rename_with_collision_strategies() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().to_path_buf()).unwrap();
+    let config = create_configuration();
+    let source = root.join("Some filename.txt");
+    let taken = root.join("Some filename -- tag.txt");
+    fs::write(&source, "source").unwrap();
+    fs::write(&taken, "taken").unwrap();
+
+    let mut path = StructuredPath::parse_from(&source, &config);
+    path.name.add_tag("tag").unwrap();
+
+    let renamer = |collision| Renamer {
+        collision,
+        allow_cross_device: false,
+    };
+
+    assert!(matches!(
+        renamer(CollisionStrategy::Fail).rename(&path),
+        Err(RenameError::Collision { .. })
+    ));
+    assert_eq!(
+        renamer(CollisionStrategy::Skip).rename(&path).unwrap(),
+        RenameOutcome::Skipped
+    );
+
+    let counter = root.join("Some filename (2) -- tag.txt");
+    match renamer(CollisionStrategy::Counter).plan(&path).unwrap() {
+        RenameOutcome::Renamed(renamed) => assert_eq!(renamed.to_string(), counter.as_str()),
+        outcome => panic!("Unexpected outcome {:?}", outcome),
+    }
+    assert!(source.exists());
+
+    // Something created after the checks is not replaced, files or directories.
+    assert!(matches!(
+        renamer(CollisionStrategy::Fail).rename_path(&source, &taken),
+        Err(RenameError::Collision { .. })
+    ));
+    assert_eq!(fs::read_to_string(&source).unwrap(), "source");
+    assert_eq!(fs::read_to_string(&taken).unwrap(), "taken");
+    let (first, second) = (root.join("first"), root.join("second"));
+    fs::create_dir(&first).unwrap();
+    fs::create_dir(&second).unwrap();
+    assert!(matches!(
+        renamer(CollisionStrategy::Skip).rename_path(&first, &second),
+        Err(RenameError::Collision { .. })
+    ));
+    assert!(first.is_dir());
+
+    renamer(CollisionStrategy::Overwrite).rename(&path).unwrap();
+    assert!(!source.exists());
+    assert_eq!(fs::read_to_string(&taken).unwrap(), "source");
+
+    assert!(matches!(
+        renamer(CollisionStrategy::Fail).rename(&path),
+        Err(RenameError::SourceMissing { .. })
+    ));
+}
+
+#[test]
+pub fn rename_case_only() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().to_path_buf()).unwrap();
+    let source = root.join("some filename.txt");
+    let target = root.join("Some filename.txt");
+    fs::write(&source, "source").unwrap();
+
+    Renamer::default().rename_path(&source, &target).unwrap();
+
+    let names: Vec<_> = fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(names, vec!["Some filename.txt".to_string()]);
+}
+
+#[test]
+pub fn walk_directory_tree() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().to_path_buf()).unwrap();
+    for path in [
+        "project/a.txt",
+        "project/photos/b.jpg",
+        "project/photos/old/c.jpg",
+        "project/.git/config",
+        "project/.tagger.toml",
+    ] {
+        fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+        fs::write(root.join(path), "").unwrap();
+    }
+    let project = root.join("project");
+    let roots = [project.clone()];
+
+    assert_eq!(
+        WalkOptions::default().walk(&roots).unwrap(),
+        vec![
+            project.join("photos/old/c.jpg"),
+            project.join("photos/b.jpg"),
+            project.join("a.txt"),
+        ]
+    );
+
+    let options = WalkOptions {
+        targets: WalkTargets::Both,
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        options.walk(&roots).unwrap(),
+        vec![
+            project.join("a.txt"),
+            project.join("photos"),
+            project.clone()
+        ]
+    );
+
+    let options = WalkOptions {
+        include: vec!["*.jpg".to_string()],
+        exclude: vec!["photos/old".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        options.walk(&roots).unwrap(),
+        vec![project.join("photos/b.jpg")]
+    );
+
+    let options = WalkOptions {
+        targets: WalkTargets::Directories,
+        ..Default::default()
+    };
+    assert_eq!(
+        options.walk(&roots).unwrap(),
+        vec![
+            project.join("photos/old"),
+            project.join("photos"),
+            project.clone()
+        ]
+    );
+
+    // Paths found from overlapping roots are kept once.
+    let options = WalkOptions {
+        targets: WalkTargets::Both,
+        ..Default::default()
+    };
+    assert_eq!(
+        options
+            .walk(&[project.clone(), project.join("photos")])
+            .unwrap(),
+        vec![
+            project.join("photos/old/c.jpg"),
+            project.join("photos/b.jpg"),
+            project.join("photos/old"),
+            project.join("a.txt"),
+            project.join("photos"),
+            project.clone()
+        ]
+    );
+
+    // Roots without a name are only walked, and paths written differently are one.
+    let options = WalkOptions {
+        targets: WalkTargets::Both,
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let parent = project.join("photos/..");
+    assert_eq!(
+        options.walk(std::slice::from_ref(&parent)).unwrap(),
+        vec![parent.join("a.txt"), parent.join("photos")]
+    );
+    assert_eq!(
+        options
+            .walk(&[project.clone(), parent, project.join("./a.txt")])
+            .unwrap(),
+        vec![
+            project.join("a.txt"),
+            project.join("photos"),
+            project.clone()
+        ]
+    );
+}
+
+#[test]
+pub fn parse_tag_expressions() {
+    let tag = |tag: &str| Box::new(TagExpression::Tag(tag.to_string()));
+
+    assert_eq!(
+        "work AND NOT draft".parse(),
+        Ok(TagExpression::And(
+            tag("work"),
+            Box::new(TagExpression::Not(tag("draft")))
+        ))
+    );
+    assert_eq!(
+        "a b OR c".parse(),
+        Ok(TagExpression::Or(
+            Box::new(TagExpression::And(tag("a"), tag("b"))),
+            tag("c")
+        ))
+    );
+    assert_eq!(
+        "a AND (b OR c)".parse(),
+        Ok(TagExpression::And(
+            tag("a"),
+            Box::new(TagExpression::Or(tag("b"), tag("c")))
+        ))
+    );
+    assert_eq!("".parse::<TagExpression>(), Err(QueryError::Empty));
+    assert_eq!(
+        "a AND".parse::<TagExpression>(),
+        Err(QueryError::UnexpectedEnd)
+    );
+    assert_eq!(
+        "a)".parse::<TagExpression>(),
+        Err(QueryError::UnexpectedToken(")".to_string()))
+    );
+    assert_eq!(
+        r#"a AND "client name"#.parse::<TagExpression>(),
+        Err(QueryError::UnterminatedQuote)
+    );
+}
+
+#[test]
+pub fn query_names() {
+    let config = create_configuration();
+    let path = create_path().join("2022-10-27 Some Invoice -- work final.pdf");
+    let name = StructuredName::parse_from(&path, &config);
+    let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day);
+
+    let matches = |query: Query| query.matches(&name);
+
+    assert!(matches(Query::default()));
+    assert!(matches(Query {
+        tags: Some("work AND NOT draft".parse().unwrap()),
+        since: date(1, 1),
+        until: date(12, 31),
+        name: Some("invoice".to_string()),
+    }));
+    assert!(!matches(Query {
+        tags: Some("work draft".parse().unwrap()),
+        ..Default::default()
+    }));
+    assert!(!matches(Query {
+        since: date(10, 28),
+        ..Default::default()
+    }));
+    assert!(!matches(Query {
+        name: Some("receipt".to_string()),
+        ..Default::default()
+    }));
+
+    // Periods match when they overlap the range, not only by their first day.
+    let period_matches = |filename: &str, since, until| {
+        let path = create_path().join(filename);
+        Query {
+            since,
+            until,
+            ..Default::default()
+        }
+        .matches(&StructuredName::parse_from(&path, &config))
+    };
+    let range = "2022-10-27 to 2022-11-03 Trip.jpg";
+    assert!(period_matches(range, date(11, 1), None));
+    assert!(period_matches(range, date(11, 3), date(11, 10)));
+    assert!(period_matches(range, None, date(10, 27)));
+    assert!(!period_matches(range, date(11, 4), None));
+    assert!(!period_matches(range, None, date(10, 26)));
+    // Monday 24 to Sunday 30 October.
+    assert!(period_matches("2022-W43 Report.pdf", date(10, 30), None));
+    assert!(!period_matches("2022-W43 Report.pdf", date(10, 31), None));
+    assert!(!period_matches("2022-W43 Report.pdf", None, date(10, 23)));
+    assert!(period_matches("2022-12 Notes.txt", date(12, 15), None));
+    assert!(period_matches("2022-02 Notes.txt", date(2, 28), date(3, 5)));
+    assert!(!period_matches("2022-02 Notes.txt", date(3, 1), None));
+    assert!(period_matches("2022 Q4 Budget.ods", date(12, 31), None));
+    assert!(period_matches("2022 Q4 Budget.ods", None, date(10, 1)));
+    assert!(!period_matches("2022 Q3 Budget.ods", date(10, 1), None));
+    assert!(!period_matches("2022 Q4 Budget.ods", None, date(9, 30)));
+}
+
+#[test]
+pub fn gather_tag_statistics() {
+    let config = create_configuration();
+    let other_folder = Utf8PathBuf::from("other");
+    let paths = [
+        create_path().join("2022-10-27 Invoice -- work final.pdf"),
+        create_path().join("2021-01-02 10h30 Invoice -- work work.pdf"),
+        other_folder.join("Notes -- work.txt"),
+        other_folder.join("Untagged.txt"),
+    ];
+
+    let mut report = TagReport::default();
+    for path in &paths {
+        report.add(&StructuredPath::parse_from(path, &config));
+    }
+
+    assert_eq!(
+        report.tags.keys().collect::<Vec<_>>(),
+        vec!["final", "work"]
+    );
+
+    let work = &report.tags["work"];
+    assert_eq!(work.count, 3);
+    assert_eq!(
+        work.first,
+        NaiveDate::from_ymd_opt(2021, 1, 2)
+            .and_then(|date| date.and_hms_opt(10, 30, 0))
+            .map(TimestampValue::DateTime)
+    );
+    assert_eq!(
+        work.last,
+        NaiveDate::from_ymd_opt(2022, 10, 27).map(TimestampValue::Date)
+    );
+    assert_eq!(
+        work.directories.iter().collect::<Vec<_>>(),
+        vec![&other_folder, &create_path()]
+    );
+
+    // Timestamps are compared as instants, whatever their timezone.
+    let mut config = create_configuration();
+    config.timestamp_configuration.default_timezone = Some(Tz::Europe__Paris);
+    let mut report = TagReport::default();
+    for name in [
+        "2022-10-27 09h00+0000 B -- zone.txt",
+        "2022-10-27 10h00+0900 A -- zone.txt",
+        "2022-10-27 05h00 C -- zone.txt",
+    ] {
+        report.add(&StructuredPath::parse_from(
+            &create_path().join(name),
+            &config,
+        ));
+    }
+    let zone = &report.tags["zone"];
+    let offset_date_time = |hour, offset| {
+        FixedOffset::east_opt(offset * 3600)
+            .and_then(|offset| offset.with_ymd_and_hms(2022, 10, 27, hour, 0, 0).single())
+            .map(TimestampValue::OffsetDateTime)
+    };
+    assert_eq!(zone.first, offset_date_time(10, 9));
+    assert_eq!(zone.last, offset_date_time(9, 0));
+}
+
+#[test]
+pub fn split_tags_on_unicode_separators() {
+    let mut config = create_configuration();
+    config.tag_configuration.tag_between_separators = vec![
+        " ".to_string(),
+        " · ".to_string(),
+        "、".to_string(),
+        "\u{a0}".to_string(),
+    ];
+
+    // The longest separator wins over the space it starts with.
+    let path = create_path().join("Notes -- work · café、école\u{a0}final.txt");
+    let name = StructuredName::parse_from(&path, &config);
+    let tags: Vec<_> = name.tags.iter().flat_map(Tags::iter).collect();
+    assert_eq!(tags, vec!["work", "café", "école", "final"]);
+    assert_eq!(
+        name.to_string(),
+        "Notes -- work · café、école\u{a0}final.txt"
+    );
+
+    let mut name = name;
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), "Notes -- work café école final.txt");
+}
+
+#[test]
+pub fn quote_tags_with_separators() {
+    let mut config = create_configuration();
+    config.tag_configuration.quotes = Some(TagQuotes {
+        open: "[".to_string(),
+        close: "]".to_string(),
+    });
+
+    // A closing quote in the middle of a tag does not end it.
+    let path = create_path().join("Report -- [client name] [work] [a]b.pdf");
+    let name = StructuredName::parse_from(&path, &config);
+    let tags: Vec<_> = name.tags.iter().flat_map(Tags::iter).collect();
+    assert_eq!(tags, vec!["client name", "work", "[a]b"]);
+    assert_eq!(name.to_string(), "Report -- [client name] [work] [a]b.pdf");
+
+    let query = Query {
+        tags: Some("\"client name\" AND work".parse().unwrap()),
+        ..Default::default()
+    };
+    assert!(query.matches(&name));
+
+    // Quotes are only written where they are needed, and cannot be around a closing quote.
+    let mut name = name;
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), "Report -- [client name] work [a]b.pdf");
+
+    let path = create_path().join("Report.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.add_tag("client name").unwrap();
+    name.add_tag("work").unwrap();
+    assert_eq!(name.to_string(), "Report -- [client name] work.pdf");
+    assert!(name.replace_tags(&["work"], "other client").unwrap());
+    assert_eq!(
+        name.to_string(),
+        "Report -- [client name] [other client].pdf"
+    );
+
+    // Tags that would not be read back are refused, the name stays as it was.
+    for tag in ["a] b", "[work]", "x -- y"] {
+        assert_eq!(
+            name.add_tag(tag),
+            Err(TagError::Unwritable {
+                tag: tag.to_string()
+            })
+        );
+    }
+    assert_eq!(
+        name.to_string(),
+        "Report -- [client name] [other client].pdf"
+    );
+
+    // A quote opened in a tag would be closed in the next ones.
+    name.add_tag("[draft").unwrap();
+    assert_eq!(
+        name.to_string(),
+        "Report -- [client name] [other client] [[draft].pdf"
+    );
+
+    // Without quotes, tags cannot contain a separator.
+    config.tag_configuration.quotes = None;
+    let mut name = StructuredName::parse_from(&path, &config);
+    assert!(name.add_tag("client name").is_err());
+    assert_eq!(name.to_string(), "Report.pdf");
+
+    // Separators may be pieces of each other, `+` is one between ` ` and ` + `.
+    config.tag_configuration.tag_between_separators = vec![" ".to_string(), " + ".to_string()];
+    assert_eq!(config.tag_configuration.quote_tag("+"), None);
+    // Such tags are kept rather than dropped, the name is not normalised at all.
+    let path = create_path().join("2022_10_27 Report -- + work.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    assert_eq!(
+        name.normalise(),
+        Err(TagError::Unwritable {
+            tag: "+".to_string()
+        })
+    );
+    assert_eq!(name.to_string(), "2022_10_27 Report -- + work.pdf");
+}
+
+#[test]
+pub fn compare_tags_in_unicode_form() {
+    let mut config = create_configuration();
+    config.tag_configuration.unicode_form = Some(UnicodeForm::Nfc);
+    let composed = "caf\u{e9}";
+    let decomposed = "cafe\u{301}";
+
+    let path = create_path().join(format!("Notes -- {decomposed} {composed}.txt"));
+    let mut name = StructuredName::parse_from(&path, &config);
+    assert!(name.remove_tag(composed));
+    assert_eq!(name.tags.iter().flat_map(Tags::iter).count(), 0);
+
+    let name = StructuredName::parse_from(&path, &config);
+    let query = Query {
+        tags: Some(composed.parse().unwrap()),
+        ..Default::default()
+    };
+    assert!(query.matches(&name));
+
+    let mut report = TagReport::default();
+    report.add(&StructuredPath::parse_from(&path, &config));
+    assert_eq!(report.tags.keys().collect::<Vec<_>>(), vec![composed]);
+    assert_eq!(report.tags[composed].count, 1);
+
+    let mut name = name;
+    name.add_tag(composed).unwrap();
+    name.normalise().unwrap();
+    assert_eq!(name.to_string(), format!("Notes -- {composed}.txt"));
+
+    // Without a form, the two spellings are different tags.
+    config.tag_configuration.unicode_form = None;
+    let mut name = StructuredName::parse_from(&path, &config);
+    assert!(name.remove_tag(composed));
+    assert_eq!(name.to_string(), format!("Notes -- {decomposed}.txt"));
+}
+
+#[test]
+pub fn key_value_tags() {
+    let mut config = create_configuration();
+    config.tag_configuration.key_value_delimiters = vec!["=".to_string(), ":".to_string()];
+    config.tag_configuration.vocabulary = Some(
+        toml::from_str(
+            r#"
+            allowed = ["status", "work"]
+            aliases = { st = "status" }
+            parents = { project = "work" }
+            "#,
+        )
+        .unwrap(),
+    );
+    let tags = &config.tag_configuration;
+
+    assert_eq!(
+        tags.split_key_value("status=final"),
+        Some(("status", "final"))
+    );
+    assert_eq!(tags.split_key_value("time:10=2"), Some(("time", "10=2")));
+    assert_eq!(tags.split_key_value("status="), None);
+    assert_eq!(
+        tags.with_ancestors("project=apollo"),
+        vec!["project=apollo", "work"]
+    );
+    assert_eq!(tags.check_tag("status:draft"), TagCheck::Known);
+    assert_eq!(
+        tags.check_tag("st=draft"),
+        TagCheck::Alias {
+            canonical: "status=draft".to_string()
+        }
+    );
+    assert_eq!(
+        tags.check_tag("statu=draft"),
+        TagCheck::Unknown {
+            closest: Some("status=draft".to_string())
+        }
+    );
+
+    // Tags with the same key are replaced where the first one was.
+    let path = create_path().join("Report -- status:draft work status=old.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.set_tag("status=final").unwrap();
+    assert_eq!(name.to_string(), "Report -- status=final work.pdf");
+
+    let path = create_path().join("Report -- work status:draft.pdf");
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.set_tag("priority=high").unwrap();
+    name.set_tag("urgent").unwrap();
+    assert_eq!(
+        name.to_string(),
+        "Report -- work status:draft priority=high urgent.pdf"
+    );
+
+    for (expression, matches) in [
+        ("status", true),
+        ("status=draft", true),
+        ("status:draft", true),
+        ("status=final", false),
+        ("draft", false),
+    ] {
+        let query = Query {
+            tags: Some(expression.parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(query.matches(&name), matches, "{expression}");
+    }
+
+    let path = create_path().join("Plan -- project=apollo.pdf");
+    let name = StructuredName::parse_from(&path, &config);
+    let query = Query {
+        tags: Some("work".parse().unwrap()),
+        ..Default::default()
+    };
+    assert!(query.matches(&name));
+
+    let mut report = TagReport::default();
+    for name in [
+        "Report -- work status:draft.pdf",
+        "Notes -- status=final status=draft.txt",
+        "Other -- status=final.txt",
+    ] {
+        report.add(&StructuredPath::parse_from(
+            &create_path().join(name),
+            &config,
+        ));
+    }
+    assert_eq!(
+        report.tags.keys().collect::<Vec<_>>(),
+        vec!["status", "work"]
+    );
+    assert_eq!(report.tags["status"].count, 3);
+    assert_eq!(
+        report.tags["status"].values.iter().collect::<Vec<_>>(),
+        vec![(&"draft".to_string(), &2), (&"final".to_string(), &2)]
+    );
+}
+
+#[test]
+pub fn check_tags_against_vocabulary() {
+    let mut config = create_configuration();
+    config.tag_configuration.vocabulary = Some(
+        toml::from_str(
+            r#"
+            allowed = ["invoice", "work"]
+            aliases = { inv = "invoice" }
+            parents = { invoice = "finance", finance = "admin" }
+            "#,
+        )
+        .unwrap(),
+    );
+    let tags = &config.tag_configuration;
+
+    assert_eq!(tags.check_tag("invoice"), TagCheck::Known);
+    assert_eq!(tags.check_tag("admin"), TagCheck::Known);
+    assert_eq!(
+        tags.check_tag("inv"),
+        TagCheck::Alias {
+            canonical: "invoice".to_string()
+        }
+    );
+    assert_eq!(
+        tags.check_tag("invoices"),
+        TagCheck::Unknown {
+            closest: Some("invoice".to_string())
+        }
+    );
+    assert_eq!(
+        tags.check_tag("holidays"),
+        TagCheck::Unknown { closest: None }
+    );
+    assert_eq!(
+        tags.with_ancestors("invoice"),
+        vec!["invoice", "finance", "admin"]
+    );
+
+    let path = create_path().join("2022-10-27 Some Invoice -- invoice.pdf");
+    let name = StructuredName::parse_from(&path, &config);
+    let query = Query {
+        tags: Some("admin AND NOT work".parse().unwrap()),
+        ..Default::default()
+    };
+    assert!(query.matches(&name));
+}
+
+#[test]
+pub fn check_tags_against_vocabulary_in_unicode_form() {
+    let mut config = create_configuration();
+    config.tag_configuration.unicode_form = Some(UnicodeForm::Nfd);
+    config.tag_configuration.tag_order = TagOrder::Vocabulary;
+    config.tag_configuration.vocabulary = Some(
+        toml::from_str(
+            r#"
+            allowed = ["été", "hiver"]
+            aliases = { "é" = "été" }
+            parents = { "été" = "saison" }
+            "#,
+        )
+        .unwrap(),
+    );
+    let tags = &config.tag_configuration;
+    let decomposed = "e\u{301}te\u{301}";
+
+    assert_eq!(tags.check_tag(decomposed), TagCheck::Known);
+    assert_eq!(
+        tags.check_tag("e\u{301}"),
+        TagCheck::Alias {
+            canonical: "\u{e9}t\u{e9}".to_string()
+        }
+    );
+    assert_eq!(tags.with_ancestors(decomposed), vec![decomposed, "saison"]);
+
+    let path = create_path().join(format!("Photos -- hiver {decomposed}.jpg"));
+    let mut name = StructuredName::parse_from(&path, &config);
+    name.normalise().unwrap();
+    assert_eq!(
+        name.to_string(),
+        format!("Photos -- {decomposed} hiver.jpg")
+    );
+}
+
+#[test]
+pub fn roll_back_transaction() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(directory.path().canonicalize().unwrap()).unwrap();
+    let journal = Journal::new(root.join("journal.jsonl"));
+    let renamer = Renamer::default();
+    for name in ["a", "b", "taken"] {
+        fs::write(root.join(name), name).unwrap();
+    }
+
+    let mut transaction = RenameTransaction::default();
+    transaction.push(root.join("a"), root.join("a -- tag"));
+    transaction.push(root.join("b"), root.join("taken"));
+    assert!(matches!(
+        transaction.commit(&renamer, &journal),
+        Err(TransactionError::RolledBack { .. })
+    ));
+    assert!(root.join("a").exists() && root.join("b").exists());
+    assert!(journal.entries().unwrap().is_empty());
+
+    let mut transaction = RenameTransaction::default();
+    transaction.push(root.join("a"), root.join("a -- tag"));
+    transaction.push(root.join("b"), root.join("b -- tag"));
+    transaction.commit(&renamer, &journal).unwrap();
+    assert!(root.join("a -- tag").exists() && root.join("b -- tag").exists());
+    assert_eq!(journal.entries().unwrap().len(), 2);
+}
+
+/// Configurations mixing formats, multi-byte separators and formats that cannot render their
+/// variant, such as a time in a date format.
+fn any_configuration() -> impl Strategy<Value = StructuredNameConfiguration> {
+    let formats = |pool: Vec<&'static str>| {
+        prop::sample::subsequence(pool.clone(), 0..=pool.len()).prop_map(|formats| {
+            TimestampVariantConfiguration {
+                formats: formats.into_iter().map(str::to_string).collect(),
+                fuzzy: None,
+            }
+        })
+    };
+
+    (
+        (
+            formats(vec![
+                "%Y-%m-%d",
+                "%Y_%m_%d",
+                "%d.%m.%Y",
+                "%Y年%m月%d日",
+                "%H",
+            ]),
+            formats(vec!["%Y-%m-%d %Hh%M", "%Y%m%dT%H%M%S", "%Y-%m-%d"]),
+            formats(vec!["%Y-%m-%d %Hh%M%z", "%Y-%m-%dT%H:%M:%S%:z"]),
+            formats(vec!["%Hh%M", "%H.%M.%S", "%Y"]),
+            formats(vec!["%Y-%m-%d--%Y-%m-%d", "%d.%m.%Y–%d.%m.%Y", "%Y"]),
+            formats(vec!["%G-W%V", "%Y"]),
+            formats(vec!["%Y-%m", "%m.%Y"]),
+            formats(vec!["%Y-Q%q", "T%q %Y", "%Y"]),
+        ),
+        prop::option::of(any::<bool>()),
+        prop::sample::subsequence(vec![" -- ", " — ", " · ", "--", "#", "ー"], 0..=3),
+        prop::sample::subsequence(vec![" ", "·", ",", "，", "+", " + ", "、", "\u{a0}"], 0..=4),
+        prop::sample::select(vec![TagOrder::Keep, TagOrder::Alphabetical]),
+        prop::option::of(prop::sample::select(vec![
+            UnicodeForm::Nfc,
+            UnicodeForm::Nfd,
+        ])),
+        prop::option::of(prop::sample::select(vec![
+            ("[", "]"),
+            ("«", "»"),
+            ("'", "'"),
+        ])),
+    )
+        .prop_map(
+            |(
+                (date, date_time, offset_date_time, time, date_range, week, month, quarter),
+                fuzzy,
+                main_separators,
+                between_separators,
+                tag_order,
+                unicode_form,
+                quotes,
+            )| StructuredNameConfiguration {
+                timestamp_configuration: TimestampConfiguration {
+                    date: TimestampVariantConfiguration {
+                        fuzzy: fuzzy.map(|month_first| FuzzyDates {
+                            order: if month_first {
+                                DayMonthOrder::MonthFirst
+                            } else {
+                                DayMonthOrder::DayFirst
+                            },
+                            ..FuzzyDates::default()
+                        }),
+                        ..date
+                    },
+                    date_time,
+                    offset_date_time,
+                    time,
+                    date_range,
+                    week,
+                    month,
+                    quarter,
+                    default_timezone: None,
+                    import: vec![],
+                },
+                tag_configuration: TagConfiguration {
+                    tag_main_separators: main_separators.into_iter().map(str::to_string).collect(),
+                    tag_between_separators: between_separators
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                    vocabulary: None,
+                    tag_order,
+                    unicode_form,
+                    quotes: quotes.map(|(open, close)| TagQuotes {
+                        open: open.to_string(),
+                        close: close.to_string(),
+                    }),
+                    key_value_delimiters: vec![],
+                },
+            },
+        )
+}
+
+/// Names made of the pieces the configurations look for, non-ASCII included.
+fn any_name() -> impl Strategy<Value = String> {
+    let piece = prop::sample::select(vec![
+        "2022-10-27",
+        "2022_1_5",
+        "27.10.2022",
+        "2022年10月27日",
+        "2022-10-27 15h35",
+        "+0200",
+        "2022-W43",
+        "2022-Q4",
+        "2022-10",
+        "--",
+        " -- ",
+        " — ",
+        " · ",
+        "ー",
+        "·",
+        "，",
+        " ",
+        "#",
+        "+",
+        "27 octobre 2022",
+        "Oct 27, 2022",
+        "Réunion",
+        "日本語",
+        "tag",
+        ".",
+        "é",
+        "e\u{301}",
+        "、",
+        "\u{a0}",
+        "[",
+        "]",
+        "«",
+        "»",
+        "'",
+    ]);
+
+    prop::collection::vec(piece, 1..8).prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #[test]
+    fn display_any_name_as_it_was(name in "[^/\\\\\\x00]{1,40}") {
+        prop_assume!(name != "." && name != "..");
+        let mut config = create_configuration();
+        config.timestamp_configuration.date.fuzzy = Some(FuzzyDates::default());
+
+        let path = Utf8PathBuf::from(name.as_str());
+        prop_assert_eq!(StructuredName::parse_from(&path, &config).to_string(), name.as_str());
+
+        // The default configuration imports timestamps, which only happens when normalising.
+        let parsed = StructuredName::parse_from(&path, &DEFAULT_CONFIGURATION);
+        prop_assert_eq!(parsed.to_string(), name);
+    }
+
+    #[test]
+    fn display_generated_name_as_it_was(
+        (year, month, day) in (1900..2100, 1u32..=12, 1u32..=28),
+        format in prop::sample::select(vec!["%Y-%m-%d", "%Y_%m_%d", "%Y-%-m-%-d", "%Y_%-m_%-d"]),
+        filename in "( [a-zA-Zéà]{1,8}){0,3}",
+        tags in prop::collection::vec("[a-z]{1,8}", 0..4),
+        extension in prop::option::of("[a-z]{1,4}"),
+    ) {
+        let config = create_configuration();
+        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+        let mut name = format!("{}{}", date.format(format), filename);
+        if !tags.is_empty() {
+            name.push_str(" -- ");
+            name.push_str(&tags.join(" "));
+        }
+        if let Some(extension) = extension {
+            name.push('.');
+            name.push_str(&extension);
+        }
+
+        let path = Utf8PathBuf::from(name.as_str());
+        let parsed = StructuredName::parse_from(&path, &config);
+        prop_assert_eq!(
+            parsed.timestamp.as_ref().map(|timestamp| timestamp.value),
+            Some(TimestampValue::Date(date))
+        );
+        prop_assert_eq!(parsed.to_string(), name.as_str());
+
+        let parsed = StructuredName::parse_from(&path, &DEFAULT_CONFIGURATION);
+        prop_assert_eq!(parsed.to_string(), name);
+    }
+
+    #[test]
+    fn parse_names_with_any_configuration(
+        config in any_configuration(),
+        name in prop_oneof![any_name(), "[^/\\\\\\x00]{1,40}"],
+    ) {
+        prop_assume!(name != "." && name != "..");
+        let path = Utf8PathBuf::from(name.as_str());
+
+        let mut parsed = StructuredPath::parse_from(&path, &config);
+        prop_assert_eq!(parsed.to_string(), name);
+
+        // Normalised names are written back as they are too, and normalising them again
+        // changes nothing. Names with tags that cannot be written back are left as they are.
+        if parsed.normalise().is_err() {
+            prop_assert_eq!(parsed.to_string(), path.as_str());
+            return Ok(());
+        }
+        let normalised = Utf8PathBuf::from(parsed.to_string());
+        let mut reparsed = StructuredPath::parse_from(&normalised, &config);
+        prop_assert_eq!(reparsed.to_string(), normalised.as_str());
+        reparsed.normalise().unwrap();
+        prop_assert_eq!(reparsed.to_string(), normalised.as_str());
+    }
+}
@@ -1,21 +1,113 @@
-use super::{Timestamp, TimestampValue};
+use super::{
+    fuzzy::FuzzyDates,
+    import::{default_import_patterns, ImportPattern},
+    Timestamp, TimestampValue,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 
 /// The timestamp formats stored on the disk.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TimestampConfiguration {
     pub date: TimestampVariantConfiguration,
     pub date_time: TimestampVariantConfiguration,
+    /// Date-times with a UTC offset (`%z` or `%:z`), tried before the others.
+    pub offset_date_time: TimestampVariantConfiguration,
+    /// Tried after the date-times and the dates.
+    pub time: TimestampVariantConfiguration,
+    /// Two dates, the end one starting at the second occurrence of the first specifier.
+    pub date_range: TimestampVariantConfiguration,
+    /// ISO weeks (`%G` and `%V`).
+    pub week: TimestampVariantConfiguration,
+    pub month: TimestampVariantConfiguration,
+    /// `%q` is the number of the quarter.
+    pub quarter: TimestampVariantConfiguration,
+    /// The timezone of the date-times without offset, such as `Europe/Paris`. The local timezone
+    /// is used when there is none.
+    pub default_timezone: Option<Tz>,
+    /// Tried in order on the names that do not start with a timestamp.
+    pub import: Vec<ImportPattern>,
 }
 
-// Contains the formats for either date or date_time. First one is the default.
-#[derive(Debug, PartialEq, Eq)]
+/// Mirrors the defaults of [date2name](https://github.com/novoid/date2name), the time alone uses
+/// the time of its date-times.
+impl Default for TimestampConfiguration {
+    fn default() -> Self {
+        TimestampConfiguration {
+            date: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m-%d".to_string()],
+                fuzzy: None,
+            },
+            date_time: TimestampVariantConfiguration {
+                formats: vec![
+                    "%Y-%m-%dT%H.%M.%S".to_string(),
+                    "%Y-%m-%dT%H.%M".to_string(),
+                ],
+                fuzzy: None,
+            },
+            offset_date_time: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m-%dT%H.%M.%S%z".to_string()],
+                fuzzy: None,
+            },
+            time: TimestampVariantConfiguration {
+                formats: vec!["%H.%M.%S".to_string()],
+                fuzzy: None,
+            },
+            date_range: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m-%d--%Y-%m-%d".to_string()],
+                fuzzy: None,
+            },
+            week: TimestampVariantConfiguration {
+                formats: vec!["%G-W%V".to_string()],
+                fuzzy: None,
+            },
+            month: TimestampVariantConfiguration {
+                formats: vec!["%Y-%m".to_string()],
+                fuzzy: None,
+            },
+            quarter: TimestampVariantConfiguration {
+                formats: vec!["%Y-Q%q".to_string()],
+                fuzzy: None,
+            },
+            default_timezone: None,
+            import: default_import_patterns(),
+        }
+    }
+}
+
+impl TimestampConfiguration {
+    /// Place a date-time without offset in the default timezone. `None` when it does not exist
+    /// there, in a daylight saving time gap. The earliest is taken when it is ambiguous.
+    pub fn with_default_timezone(&self, date_time: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self.default_timezone {
+            Some(timezone) => timezone
+                .from_local_datetime(&date_time)
+                .earliest()
+                .map(|date_time| date_time.fixed_offset()),
+            None => Local
+                .from_local_datetime(&date_time)
+                .earliest()
+                .map(|date_time| date_time.fixed_offset()),
+        }
+    }
+}
+
+// Contains the formats for either date, date_time or time. First one is the default.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct TimestampVariantConfiguration {
     /// First one is the default.
     pub formats: Vec<String>,
+    /// Also read the dates written without any of the formats, see [FuzzyDates]. Only used by
+    /// the dates, disabled by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzzy: Option<FuzzyDates>,
 }
 
 impl TimestampVariantConfiguration {
-    pub fn formats(&self) -> TimestampFormatIterator {
+    pub fn formats(&self) -> TimestampFormatIterator<'_> {
         TimestampFormatIterator {
             configuration: self,
             format_index: None,
@@ -31,11 +123,12 @@ pub struct TimestampFormat<'c> {
 }
 
 impl<'c> TimestampFormat<'c> {
-    pub fn new_timestamp(&self, date: TimestampValue) -> Timestamp<'c> {
+    pub fn new_timestamp<'p>(&self, date: TimestampValue) -> Timestamp<'p, 'c> {
         Timestamp {
             configuration: self.configuration,
             format_index: self.format_index,
             value: date,
+            original: None,
         }
     }
 }
@@ -0,0 +1,215 @@
+//! Dates written by hand rather than with a format: month names in English and French
+//! (`27 octobre 2022`, `Oct 27 2022`), two-digit years and numeric dates whose day/month order is
+//! given by the configuration (`05.04.22`).
+
+use super::{configuration::TimestampVariantConfiguration, Timestamp, TimestampValue};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// How to read the dates that do not match any format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzyDates {
+    /// The order of numeric dates when both numbers could be a month.
+    pub order: DayMonthOrder,
+    /// Two-digit years below it are in the 2000s, the others in the 1900s.
+    pub century_pivot: u8,
+}
+
+impl Default for FuzzyDates {
+    fn default() -> Self {
+        FuzzyDates {
+            order: DayMonthOrder::DayFirst,
+            century_pivot: 70,
+        }
+    }
+}
+
+/// Which of the day or the month comes first in numeric dates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayMonthOrder {
+    /// `27.10.22`, as in British English and French.
+    #[default]
+    DayFirst,
+    /// `10.27.22`, as in American English.
+    MonthFirst,
+}
+
+/// Full names and the usual abbreviations, accents are optional.
+const MONTH_NAMES: &[(&str, u32)] = &[
+    ("january", 1),
+    ("jan", 1),
+    ("janvier", 1),
+    ("janv", 1),
+    ("february", 2),
+    ("feb", 2),
+    ("fevrier", 2),
+    ("fevr", 2),
+    ("fev", 2),
+    ("march", 3),
+    ("mar", 3),
+    ("mars", 3),
+    ("april", 4),
+    ("apr", 4),
+    ("avril", 4),
+    ("avr", 4),
+    ("may", 5),
+    ("mai", 5),
+    ("june", 6),
+    ("jun", 6),
+    ("juin", 6),
+    ("july", 7),
+    ("jul", 7),
+    ("juillet", 7),
+    ("juil", 7),
+    ("august", 8),
+    ("aug", 8),
+    ("aout", 8),
+    ("september", 9),
+    ("sep", 9),
+    ("sept", 9),
+    ("septembre", 9),
+    ("october", 10),
+    ("oct", 10),
+    ("octobre", 10),
+    ("november", 11),
+    ("nov", 11),
+    ("novembre", 11),
+    ("december", 12),
+    ("dec", 12),
+    ("decembre", 12),
+];
+
+impl TimestampVariantConfiguration {
+    /// Read a date at the start of the name without the formats, when fuzzy dates are enabled.
+    pub fn parse_fuzzy_date<'p, 'c>(&'c self, filename: &mut &str) -> Option<Timestamp<'p, 'c>> {
+        let fuzzy = self.fuzzy?;
+        let tokens = tokens(filename);
+        let [first, second, third] = [tokens.first()?, tokens.get(1)?, tokens.get(2)?];
+        // What follows the digits of the year is not part of the date, it may be a tag separator.
+        let year_length = third
+            .1
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(third.1.len());
+        let year = &third.1[..year_length];
+
+        let date = if let Some(month) = month(first.1) {
+            // Oct 27 2022
+            date(fuzzy, year, month, day(second.1)?)
+        } else if let Some(month) = month(second.1) {
+            // 27 octobre 2022
+            date(fuzzy, year, month, day(first.1)?)
+        } else {
+            // 27.10.22, only with two digits for the day and the month to leave version numbers
+            // such as `1.2.3` alone.
+            let [first, second] = [first.1, second.1].map(|number| {
+                (number.len() == 2)
+                    .then(|| number.parse::<u32>().ok())
+                    .flatten()
+            });
+            let (first, second) = (first?, second?);
+            let (day, month) = match fuzzy.order {
+                _ if first > 12 => (first, second),
+                _ if second > 12 => (second, first),
+                DayMonthOrder::DayFirst => (first, second),
+                DayMonthOrder::MonthFirst => (second, first),
+            };
+            date(fuzzy, year, month, day)
+        }?;
+
+        *filename = &filename[third.0 + year_length..];
+        Some(Timestamp {
+            configuration: self,
+            format_index: 0,
+            value: TimestampValue::Date(date),
+            original: None,
+        })
+    }
+}
+
+/// The first words and numbers of the name with their position. Only spaces and punctuation
+/// may separate them, and nothing may come before the first one.
+fn tokens(filename: &str) -> Vec<(usize, &str)> {
+    let is_separator = |c: char| matches!(c, ' ' | '.' | ',' | '-' | '_');
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while tokens.len() < 3 && start < filename.len() {
+        let rest = &filename[start..];
+        let length = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        if length == 0 {
+            break;
+        }
+        tokens.push((start, &rest[..length]));
+
+        let after = &rest[length..];
+        let separators = after
+            .find(|c: char| !is_separator(c))
+            .unwrap_or(after.len());
+        // The date ends at its last token, separators are only needed in between.
+        if separators == 0 || separators > 2 {
+            break;
+        }
+        start += length + separators;
+    }
+
+    tokens
+}
+
+fn month(word: &str) -> Option<u32> {
+    let word: String = word
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'é' | 'è' | 'ê' => 'e',
+            'û' | 'ù' => 'u',
+            'à' | 'â' => 'a',
+            c => c,
+        })
+        .collect();
+
+    MONTH_NAMES
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, month)| *month)
+}
+
+/// `27`, `27th`, `1st` or `1er`.
+fn day(word: &str) -> Option<u32> {
+    let digits = word
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(word.len());
+    let (number, suffix) = word.split_at(digits);
+
+    let suffixes = ["", "st", "nd", "rd", "th", "er"];
+    if number.is_empty() || number.len() > 2 || !suffixes.contains(&suffix.to_lowercase().as_str())
+    {
+        return None;
+    }
+
+    number.parse().ok()
+}
+
+fn date(fuzzy: FuzzyDates, year: &str, month: u32, day: u32) -> Option<NaiveDate> {
+    if !year.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let year = match year.len() {
+        4 => year.parse().ok()?,
+        2 => {
+            let year: i32 = year.parse().ok()?;
+            if year < i32::from(fuzzy.century_pivot) {
+                2000 + year
+            } else {
+                1900 + year
+            }
+        }
+        _ => return None,
+    };
+
+    NaiveDate::from_ymd_opt(year, month, day)
+}
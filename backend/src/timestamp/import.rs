@@ -0,0 +1,119 @@
+//! Names given by cameras and other applications, such as `IMG_20221027_153500`, have their
+//! timestamp in the middle of other text. Import patterns find it, so that it is lifted to the
+//! front in the main format and the surrounding noise is dropped when the name is normalised.
+//! Parsing alone keeps the name as it is.
+
+use super::{configuration::TimestampConfiguration, Timestamp, TimestampValue};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A regular expression with a `timestamp` group, read with a chrono format. Everything else the
+/// expression matches is removed from the name.
+///
+/// ```toml
+/// [[timestamp.import]]
+/// pattern = '^VID-(?P<timestamp>\d{8})-'
+/// format = "%Y%m%d"
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImportPattern {
+    #[serde(
+        deserialize_with = "deserialize_regex",
+        serialize_with = "serialize_regex"
+    )]
+    pub pattern: Regex,
+    pub format: String,
+}
+
+impl PartialEq for ImportPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern.as_str() == other.pattern.as_str() && self.format == other.format
+    }
+}
+
+impl Eq for ImportPattern {}
+
+impl ImportPattern {
+    /// Panics on an invalid pattern, meant for the built-in ones.
+    pub fn new(pattern: &str, format: &str) -> Self {
+        ImportPattern {
+            pattern: Regex::new(pattern).unwrap(),
+            format: format.to_string(),
+        }
+    }
+
+    /// Read the timestamp, the offset date-times first like in names.
+    fn parse(&self, text: &str) -> Option<TimestampValue> {
+        if let Ok(date_time) = DateTime::parse_from_str(text, &self.format) {
+            return Some(TimestampValue::OffsetDateTime(date_time));
+        }
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(text, &self.format) {
+            return Some(TimestampValue::DateTime(date_time));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(text, &self.format) {
+            return Some(TimestampValue::Date(date));
+        }
+        NaiveTime::parse_from_str(text, &self.format)
+            .ok()
+            .map(TimestampValue::Time)
+    }
+}
+
+/// Names of Android and Pixel cameras, WhatsApp and the GNOME and Android screenshots.
+pub fn default_import_patterns() -> Vec<ImportPattern> {
+    vec![
+        ImportPattern::new(
+            r"^(?:IMG|VID|PXL|MVIMG)_(?P<timestamp>\d{8}_\d{6})\d*",
+            "%Y%m%d_%H%M%S",
+        ),
+        ImportPattern::new(
+            r"^Screenshot from (?P<timestamp>\d{4}-\d{2}-\d{2} \d{2}-\d{2}-\d{2})",
+            "%Y-%m-%d %H-%M-%S",
+        ),
+        ImportPattern::new(r"^Screenshot_(?P<timestamp>\d{8}-\d{6})", "%Y%m%d-%H%M%S"),
+        // The WhatsApp counter is kept, it tells apart the pictures of the same day.
+        ImportPattern::new(r"^(?:IMG|VID)-(?P<timestamp>\d{8})-", "%Y%m%d"),
+    ]
+}
+
+impl TimestampConfiguration {
+    /// Find a timestamp anywhere in the name with the first matching import pattern. Return it
+    /// with the main format of its variant, along with what is left of the name once the match
+    /// and the separators around it are removed.
+    pub fn import<'p, 'c>(&'c self, filename: &str) -> Option<(Timestamp<'p, 'c>, String)> {
+        self.import.iter().find_map(|pattern| {
+            let captures = pattern.pattern.captures(filename)?;
+            let value = pattern.parse(captures.name("timestamp")?.as_str())?;
+            let matched = captures.get(0)?;
+
+            let is_noise = |c: char| c.is_whitespace() || matches!(c, '_' | '-' | '.');
+            let before = filename[..matched.start()].trim_matches(is_noise);
+            let after = filename[matched.end()..].trim_matches(is_noise);
+            let remainder = match (before.is_empty(), after.is_empty()) {
+                (false, false) => format!("{before} {after}"),
+                _ => format!("{before}{after}"),
+            };
+
+            Some((self.new_timestamp(value), remainder))
+        })
+    }
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    let regex = Regex::new(&pattern).map_err(de::Error::custom)?;
+
+    if !regex.capture_names().any(|name| name == Some("timestamp")) {
+        return Err(de::Error::custom(format!(
+            "the import pattern `{pattern}` has no `timestamp` group"
+        )));
+    }
+
+    Ok(regex)
+}
+
+fn serialize_regex<S: Serializer>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(regex.as_str())
+}
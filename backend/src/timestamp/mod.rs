@@ -1,64 +1,258 @@
 use self::configuration::{TimestampConfiguration, TimestampFormat, TimestampVariantConfiguration};
-use chrono::{NaiveDate, NaiveDateTime};
-use std::fmt;
+use chrono::{DateTime, Days, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::fmt::{self, Write};
 
 pub mod configuration;
+pub mod fuzzy;
+pub mod import;
+pub mod period;
+pub mod source;
 
-/// Temporal data (and its format) found in the file/directory name. May be a date or a datetime.
-#[derive(Debug, PartialEq, Eq)]
-pub struct Timestamp<'c> {
+pub use self::fuzzy::{DayMonthOrder, FuzzyDates};
+pub use self::import::ImportPattern;
+pub use self::source::{TimestampKind, TimestampSource};
+
+/// Temporal data (and its format) found in the file/directory name. May be a date, a datetime
+/// with or without offset, a time or a period.
+///
+/// Parsed timestamps borrow the path, they are written back as they were read until they are
+/// changed or normalised: formats do not always render what they read, such as `2022-1-5` read
+/// with `%Y-%m-%d`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timestamp<'p, 'c> {
     pub configuration: &'c TimestampVariantConfiguration,
     pub format_index: usize,
     pub value: TimestampValue,
+    /// The text it was parsed from, to be cleared when the value changes.
+    pub original: Option<&'p str>,
 }
 
 /// What type of temporal data are we talking about here.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
 pub enum TimestampValue {
     Date(NaiveDate),
     DateTime(NaiveDateTime),
+    OffsetDateTime(DateTime<FixedOffset>),
+    Time(NaiveTime),
+    /// First and last days, both included.
+    DateRange(NaiveDate, NaiveDate),
+    /// The Monday of an ISO week.
+    #[serde(serialize_with = "period::serialize_week")]
+    Week(NaiveDate),
+    /// The first day of a month.
+    #[serde(serialize_with = "period::serialize_month")]
+    Month(NaiveDate),
+    /// The first day of a quarter.
+    #[serde(serialize_with = "period::serialize_quarter")]
+    Quarter(NaiveDate),
 }
 
-impl<'c> Timestamp<'c> {
+impl TimestampValue {
+    /// The day of the timestamp, to compare timestamps of different variants. There is none for
+    /// a time alone. Date-times with an offset use the day in their own offset, periods their
+    /// first day.
+    pub fn date(&self) -> Option<NaiveDate> {
+        match self {
+            TimestampValue::Date(date)
+            | TimestampValue::DateRange(date, _)
+            | TimestampValue::Week(date)
+            | TimestampValue::Month(date)
+            | TimestampValue::Quarter(date) => Some(*date),
+            TimestampValue::DateTime(date_time) => Some(date_time.date()),
+            TimestampValue::OffsetDateTime(date_time) => Some(date_time.date_naive()),
+            TimestampValue::Time(_) => None,
+        }
+    }
+
+    /// The last day covered by the timestamp: the end of a range, the Sunday of a week, the last
+    /// day of a month or quarter, and the day itself otherwise.
+    pub fn last_day(&self) -> Option<NaiveDate> {
+        match self {
+            TimestampValue::DateRange(_, end) => Some(*end),
+            TimestampValue::Week(monday) => monday.checked_add_days(Days::new(6)),
+            TimestampValue::Month(first_day) => period::last_day(*first_day, 1),
+            TimestampValue::Quarter(first_day) => period::last_day(*first_day, 3),
+            _ => self.date(),
+        }
+    }
+}
+
+impl<'p, 'c> Timestamp<'p, 'c> {
     /// Normalisation is when we want to use the main format (listed first) to keep everything
     /// tidy, instead of the one previously used.
     pub fn normalise(&mut self) {
-        self.format_index = 0
+        self.format_index = 0;
+        self.original = None;
     }
 }
 
-impl<'c> fmt::Display for Timestamp<'c> {
+impl<'p, 'c> fmt::Display for Timestamp<'p, 'c> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let format = self.configuration.formats.get(self.format_index).unwrap();
-        match self.value {
-            TimestampValue::Date(date) => write!(f, "{}", date.format(format))?,
-            TimestampValue::DateTime(date_time) => write!(f, "{}", date_time.format(format))?,
+        if let Some(original) = self.original {
+            return f.write_str(original);
+        }
+
+        // Some formats cannot render the value, such as a time specifier in a date format: the
+        // others are tried, then the default one.
+        let formats = &self.configuration.formats;
+        let rendered = formats
+            .get(self.format_index)
+            .into_iter()
+            .chain(formats)
+            .find_map(|format| self.value.render(format))
+            .or_else(|| self.value.render(self.value.fallback_format()))
+            .unwrap_or_default();
+
+        f.write_str(&rendered)
+    }
+}
+
+impl TimestampValue {
+    /// `None` when the format cannot render the value.
+    pub fn render(&self, format: &str) -> Option<String> {
+        let mut rendered = String::new();
+        let result = match *self {
+            TimestampValue::Date(date) => write!(rendered, "{}", date.format(format)),
+            TimestampValue::DateTime(date_time) => write!(rendered, "{}", date_time.format(format)),
+            TimestampValue::OffsetDateTime(date_time) => {
+                write!(rendered, "{}", date_time.format(format))
+            }
+            TimestampValue::Time(time) => write!(rendered, "{}", time.format(format)),
+            TimestampValue::DateRange(start, end) => {
+                period::write_date_range(&mut rendered, format, start, end)
+            }
+            TimestampValue::Week(monday) => write!(rendered, "{}", monday.format(format)),
+            TimestampValue::Month(first_day) => write!(rendered, "{}", first_day.format(format)),
+            TimestampValue::Quarter(first_day) => {
+                period::write_quarter(&mut rendered, format, first_day)
+            }
+        };
+
+        result.ok().map(|_| rendered)
+    }
+
+    /// The built-in main format of the variant.
+    fn fallback_format(&self) -> &'static str {
+        match self {
+            TimestampValue::Date(_) => "%Y-%m-%d",
+            TimestampValue::DateTime(_) => "%Y-%m-%dT%H.%M.%S",
+            TimestampValue::OffsetDateTime(_) => "%Y-%m-%dT%H.%M.%S%z",
+            TimestampValue::Time(_) => "%H.%M.%S",
+            TimestampValue::DateRange(..) => "%Y-%m-%d--%Y-%m-%d",
+            TimestampValue::Week(_) => "%G-W%V",
+            TimestampValue::Month(_) => "%Y-%m",
+            TimestampValue::Quarter(_) => "%Y-Q%q",
         }
-        Ok(())
     }
 }
 
 impl TimestampConfiguration {
+    /// Build a timestamp using the main format of the matching variant.
+    pub fn new_timestamp<'p>(&self, value: TimestampValue) -> Timestamp<'p, '_> {
+        let configuration = match value {
+            TimestampValue::Date(_) => &self.date,
+            TimestampValue::DateTime(_) => &self.date_time,
+            TimestampValue::OffsetDateTime(_) => &self.offset_date_time,
+            TimestampValue::Time(_) => &self.time,
+            TimestampValue::DateRange(..) => &self.date_range,
+            TimestampValue::Week(_) => &self.week,
+            TimestampValue::Month(_) => &self.month,
+            TimestampValue::Quarter(_) => &self.quarter,
+        };
+
+        Timestamp {
+            configuration,
+            format_index: 0,
+            value,
+            original: None,
+        }
+    }
+
     /// Try to read temporal data in the file/directory name. Subtract the temporal data from name.
-    pub fn parse<'c>(&'c self, filename: &mut &str) -> Option<Timestamp<'c>> {
+    pub fn parse<'p, 'c>(&'c self, filename: &mut &'p str) -> Option<Timestamp<'p, 'c>> {
+        let start = *filename;
+        let timestamp = self.parse_value(filename)?;
+
+        Some(Timestamp {
+            original: Some(&start[..start.len() - filename.len()]),
+            ..timestamp
+        })
+    }
+
+    fn parse_value<'p, 'c>(&'c self, filename: &mut &str) -> Option<Timestamp<'p, 'c>> {
+        // An offset would be left in the file name by the formats without it.
+        for format in self.offset_date_time.formats() {
+            if let Some(timestamp) = format.parse_offset_date_time(filename) {
+                return Some(timestamp);
+            }
+        }
+
         for format in self.date_time.formats() {
             if let Some(timestamp) = format.parse_date_time(filename) {
                 return Some(timestamp);
             }
         }
 
+        // A date would leave the end of the range in the file name.
+        for format in self.date_range.formats() {
+            if let Some(timestamp) = format.parse_date_range(filename) {
+                return Some(timestamp);
+            }
+        }
+
         for format in self.date.formats() {
             if let Some(timestamp) = format.parse_date(filename) {
                 return Some(timestamp);
             }
         }
 
+        // Numbers such as `09.30.00` are left to the time formats reading them exactly.
+        let mut fuzzy_remainder = *filename;
+        if let Some(timestamp) = self.date.parse_fuzzy_date(&mut fuzzy_remainder) {
+            let is_time = self.time.formats().any(|format| {
+                let mut time_remainder = *filename;
+                format.parse_time(&mut time_remainder).is_some()
+                    && time_remainder.len() <= fuzzy_remainder.len()
+            });
+            if !is_time {
+                *filename = fuzzy_remainder;
+                return Some(timestamp);
+            }
+        }
+
+        for format in self.week.formats() {
+            if let Some(timestamp) = format.parse_week(filename) {
+                return Some(timestamp);
+            }
+        }
+
+        for format in self.quarter.formats() {
+            if let Some(timestamp) = format.parse_quarter(filename) {
+                return Some(timestamp);
+            }
+        }
+
+        // After the dates, which start the same way.
+        for format in self.month.formats() {
+            if let Some(timestamp) = format.parse_month(filename) {
+                return Some(timestamp);
+            }
+        }
+
+        for format in self.time.formats() {
+            if let Some(timestamp) = format.parse_time(filename) {
+                return Some(timestamp);
+            }
+        }
+
         None
     }
 }
 
 impl<'c> TimestampFormat<'c> {
-    pub fn parse_date<'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'c>> {
+    pub fn parse_date<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
         if let Ok((date, remainder)) = NaiveDate::parse_and_remainder(filename, self.format) {
             *filename = remainder;
             Some(self.new_timestamp(TimestampValue::Date(date)))
@@ -67,7 +261,7 @@ impl<'c> TimestampFormat<'c> {
         }
     }
 
-    pub fn parse_date_time<'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'c>> {
+    pub fn parse_date_time<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
         if let Ok((date, remainder)) = NaiveDateTime::parse_and_remainder(filename, self.format) {
             *filename = remainder;
             Some(self.new_timestamp(TimestampValue::DateTime(date)))
@@ -75,4 +269,32 @@ impl<'c> TimestampFormat<'c> {
             None
         }
     }
+
+    pub fn parse_offset_date_time<'p, 'f>(
+        &self,
+        filename: &'f mut &str,
+    ) -> Option<Timestamp<'p, 'c>> {
+        if let Ok((date, remainder)) = DateTime::parse_and_remainder(filename, self.format) {
+            *filename = remainder;
+            Some(self.new_timestamp(TimestampValue::OffsetDateTime(date)))
+        } else {
+            None
+        }
+    }
+
+    /// Unlike dates, the time must be written exactly as the format would: names starting with
+    /// a version number such as `1.2.3` are not times. Formats that cannot render a time alone,
+    /// with a date in them, never match.
+    pub fn parse_time<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
+        let (time, remainder) = NaiveTime::parse_and_remainder(filename, self.format).ok()?;
+        let parsed = &filename[..filename.len() - remainder.len()];
+
+        let value = TimestampValue::Time(time);
+        if value.render(self.format).as_deref() != Some(parsed) {
+            return None;
+        }
+
+        *filename = remainder;
+        Some(self.new_timestamp(value))
+    }
 }
@@ -0,0 +1,172 @@
+//! Timestamps covering more than a day: date ranges, ISO weeks, months and quarters. Periods are
+//! stored as dates (the first day of the period) and read with the chrono formats, quarters use
+//! `%q` for their number.
+
+use super::{configuration::TimestampFormat, Timestamp, TimestampValue};
+use chrono::{
+    format::{parse_and_remainder, Parsed, StrftimeItems},
+    Datelike, Months, NaiveDate, Weekday,
+};
+use serde::Serializer;
+use std::fmt;
+
+/// Placeholder of the quarter number, unknown to chrono.
+const QUARTER: &str = "%q";
+
+impl<'c> TimestampFormat<'c> {
+    /// The end date starts at the second occurrence of the first specifier, such as
+    /// `%Y-%m-%d--%Y-%m-%d`. Chrono skips the whitespaces before numbers, the range must be
+    /// written exactly as the format renders it so that normalising does not change it.
+    pub fn parse_date_range<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
+        let (start_format, end_format) = split_range_format(self.format)?;
+        let (start, remainder) = NaiveDate::parse_and_remainder(filename, start_format).ok()?;
+        let (end, remainder) = NaiveDate::parse_and_remainder(remainder, end_format).ok()?;
+
+        if end < start {
+            return None;
+        }
+
+        let value = TimestampValue::DateRange(start, end);
+        let parsed = &filename[..filename.len() - remainder.len()];
+        if value.render(self.format).as_deref() != Some(parsed) {
+            return None;
+        }
+
+        *filename = remainder;
+        Some(self.new_timestamp(value))
+    }
+
+    /// Stored as the Monday of the week.
+    pub fn parse_week<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
+        let (mut parsed, remainder) = parse_parts(filename, self.format)?;
+        parsed.set_weekday(Weekday::Mon).ok()?;
+        let monday = parsed.to_naive_date().ok()?;
+
+        *filename = remainder;
+        Some(self.new_timestamp(TimestampValue::Week(monday)))
+    }
+
+    /// Stored as the first day of the month.
+    pub fn parse_month<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
+        let (mut parsed, remainder) = parse_parts(filename, self.format)?;
+        parsed.set_day(1).ok()?;
+        let first_day = parsed.to_naive_date().ok()?;
+
+        *filename = remainder;
+        Some(self.new_timestamp(TimestampValue::Month(first_day)))
+    }
+
+    /// Stored as the first day of the quarter.
+    pub fn parse_quarter<'p, 'f>(&self, filename: &'f mut &str) -> Option<Timestamp<'p, 'c>> {
+        let (before, after) = self.format.split_once(QUARTER)?;
+
+        let (mut parsed, remainder) = parse_parts(filename, before)?;
+        let quarter = remainder
+            .chars()
+            .next()
+            .and_then(|digit| digit.to_digit(10))
+            .filter(|quarter| (1..=4).contains(quarter))?;
+        let (parsed_after, remainder) = parse_parts(&remainder[1..], after)?;
+
+        parsed.year = parsed.year.or(parsed_after.year);
+        parsed.set_month(i64::from(quarter * 3 - 2)).ok()?;
+        parsed.set_day(1).ok()?;
+        let first_day = parsed.to_naive_date().ok()?;
+
+        *filename = remainder;
+        Some(self.new_timestamp(TimestampValue::Quarter(first_day)))
+    }
+}
+
+fn parse_parts<'f>(text: &'f str, format: &str) -> Option<(Parsed, &'f str)> {
+    let mut parsed = Parsed::new();
+    let remainder = parse_and_remainder(&mut parsed, text, StrftimeItems::new(format)).ok()?;
+    Some((parsed, remainder))
+}
+
+/// Split a range format in the formats of the start and of the end.
+fn split_range_format(format: &str) -> Option<(&str, &str)> {
+    let start = format.find('%')?;
+    // Padding modifiers such as `%-d` are part of the specifier.
+    let length = match format[start + 1..].chars().next()? {
+        '-' | '_' | '0' => 3,
+        _ => 2,
+    };
+    let specifier = format.get(start..start + length)?;
+    let end = start + length + format[start + length..].find(specifier)?;
+
+    Some(format.split_at(end))
+}
+
+pub(super) fn write_date_range(
+    f: &mut impl fmt::Write,
+    format: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> fmt::Result {
+    match split_range_format(format) {
+        Some((start_format, end_format)) => write!(
+            f,
+            "{}{}",
+            start.format(start_format),
+            end.format(end_format)
+        ),
+        None => Err(fmt::Error),
+    }
+}
+
+pub(super) fn write_quarter(
+    f: &mut impl fmt::Write,
+    format: &str,
+    first_day: NaiveDate,
+) -> fmt::Result {
+    match format.split_once(QUARTER) {
+        Some((before, after)) => write!(
+            f,
+            "{}{}{}",
+            first_day.format(before),
+            quarter(first_day),
+            first_day.format(after)
+        ),
+        None => Err(fmt::Error),
+    }
+}
+
+/// The day before the first day `months` later.
+pub(super) fn last_day(first_day: NaiveDate, months: u32) -> Option<NaiveDate> {
+    first_day
+        .checked_add_months(Months::new(months))?
+        .pred_opt()
+}
+
+fn quarter(date: NaiveDate) -> u32 {
+    date.month0() / 3 + 1
+}
+
+/// `2022-W43`, whatever the formats of the names.
+pub(super) fn serialize_week<S: Serializer>(
+    monday: &NaiveDate,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&monday.format("%G-W%V"))
+}
+
+/// `2022-10`, whatever the formats of the names.
+pub(super) fn serialize_month<S: Serializer>(
+    first_day: &NaiveDate,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&first_day.format("%Y-%m"))
+}
+
+/// `2022-Q4`, whatever the formats of the names.
+pub(super) fn serialize_quarter<S: Serializer>(
+    first_day: &NaiveDate,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!(
+        "{}-Q{}",
+        first_day.year(),
+        quarter(*first_day)
+    ))
+}
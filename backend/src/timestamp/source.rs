@@ -0,0 +1,123 @@
+use super::{configuration::TimestampConfiguration, Timestamp, TimestampValue};
+use crate::{ExtractError, Extractors};
+use camino::Utf8Path;
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, time::SystemTime};
+
+/// Where a new timestamp comes from, as in [date2name](https://github.com/novoid/date2name).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSource {
+    /// The current time.
+    #[default]
+    Now,
+    /// The last modification of the file/directory.
+    Modified,
+    /// The creation (birth) of the file/directory, not every filesystem keeps it.
+    Created,
+    /// The oldest of the modification and the creation, when it is known.
+    Oldest,
+    /// The metadata inside the file: capture date of photos and videos, creation date of PDFs.
+    Embedded,
+}
+
+/// Which variant of timestamp to build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampKind {
+    Date,
+    DateTime,
+    Time,
+}
+
+impl TimestampSource {
+    /// Fails when the filesystem does not know the requested time.
+    pub fn system_time(&self, metadata: &fs::Metadata) -> io::Result<SystemTime> {
+        match self {
+            TimestampSource::Now => Ok(SystemTime::now()),
+            TimestampSource::Modified => metadata.modified(),
+            TimestampSource::Created => metadata.created(),
+            TimestampSource::Oldest => {
+                let modified = metadata.modified()?;
+                Ok(match metadata.created() {
+                    Ok(created) => created.min(modified),
+                    Err(_) => modified,
+                })
+            }
+            TimestampSource::Embedded => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "embedded timestamps are not in the metadata of the filesystem",
+            )),
+        }
+    }
+}
+
+impl TimestampConfiguration {
+    /// A timestamp in the main format of its variant, from the metadata of a file/directory.
+    pub fn timestamp_from_metadata(
+        &self,
+        metadata: &fs::Metadata,
+        source: TimestampSource,
+        kind: TimestampKind,
+    ) -> io::Result<Timestamp<'static, '_>> {
+        let date_time = self.local_date_time(source.system_time(metadata)?);
+
+        Ok(self.new_timestamp(value_of_kind(date_time, kind)))
+    }
+
+    /// A timestamp in the main format of its variant, from any source. `None` when the file has no
+    /// embedded timestamp, or when it cannot give the requested kind.
+    pub fn timestamp_for(
+        &self,
+        path: &Utf8Path,
+        source: TimestampSource,
+        kind: TimestampKind,
+        extractors: &Extractors,
+    ) -> Result<Option<Timestamp<'static, '_>>, ExtractError> {
+        let io_error = |source| ExtractError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        if source != TimestampSource::Embedded {
+            let metadata = fs::symlink_metadata(path).map_err(io_error)?;
+            return self
+                .timestamp_from_metadata(&metadata, source, kind)
+                .map(Some)
+                .map_err(io_error);
+        }
+
+        // Videos are dated in UTC, they are placed in the default timezone like the others.
+        let date_time = match extractors.extract(path)? {
+            None | Some(TimestampValue::Time(_)) => return Ok(None),
+            Some(TimestampValue::DateTime(date_time)) => Some(date_time),
+            Some(TimestampValue::OffsetDateTime(date_time)) => {
+                Some(self.local_date_time(date_time.into()))
+            }
+            // No time is made up when only the date or the period is known.
+            Some(value) => {
+                return Ok((kind != TimestampKind::Time).then(|| self.new_timestamp(value)))
+            }
+        };
+
+        Ok(date_time.map(|date_time| self.new_timestamp(value_of_kind(date_time, kind))))
+    }
+
+    /// The date-time in the default timezone, or the local one when there is none.
+    pub fn local_date_time(&self, time: SystemTime) -> NaiveDateTime {
+        match self.default_timezone {
+            Some(timezone) => DateTime::<Utc>::from(time)
+                .with_timezone(&timezone)
+                .naive_local(),
+            None => DateTime::<Local>::from(time).naive_local(),
+        }
+    }
+}
+
+fn value_of_kind(date_time: NaiveDateTime, kind: TimestampKind) -> TimestampValue {
+    match kind {
+        TimestampKind::Date => TimestampValue::Date(date_time.date()),
+        TimestampKind::DateTime => TimestampValue::DateTime(date_time),
+        TimestampKind::Time => TimestampValue::Time(date_time.time()),
+    }
+}
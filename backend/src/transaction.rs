@@ -0,0 +1,103 @@
+//! Rename many files/directories as a whole: when one rename fails, the previous ones are undone.
+
+use crate::{is_taken, Journal, JournalError, RenameError, Renamer};
+use camino::{Utf8Path, Utf8PathBuf};
+use thiserror::Error;
+
+/// Everything that can go wrong while committing a transaction.
+#[derive(Debug, Error)]
+pub enum TransactionError {
+    #[error("cannot rename {from}, the previous renames were rolled back")]
+    RolledBack {
+        from: Utf8PathBuf,
+        #[source]
+        source: RenameError,
+    },
+    /// The files are left half renamed, `failed` is the rename that started the rollback.
+    #[error("cannot rename {failed}, then cannot roll back the rename of {from}")]
+    RollbackFailed {
+        failed: Utf8PathBuf,
+        from: Utf8PathBuf,
+        #[source]
+        source: RenameError,
+    },
+    #[error("the renames are done but cannot be recorded in the journal")]
+    Journal(#[source] JournalError),
+}
+
+/// Renames to do in order, see [RenameTransaction::commit].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenameTransaction {
+    renames: Vec<(Utf8PathBuf, Utf8PathBuf)>,
+}
+
+impl RenameTransaction {
+    pub fn push(&mut self, from: Utf8PathBuf, to: Utf8PathBuf) {
+        self.renames.push((from, to));
+    }
+
+    pub fn renames(&self) -> &[(Utf8PathBuf, Utf8PathBuf)] {
+        &self.renames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// Do every rename, never overwriting anything. On the first failure, the renames already
+    /// done are undone, the most recent first. Once everything is renamed, the renames are
+    /// recorded in the journal.
+    pub fn commit(&self, renamer: &Renamer, journal: &Journal) -> Result<(), TransactionError> {
+        for (index, (from, to)) in self.renames.iter().enumerate() {
+            if let Err(source) = rename_once(renamer, from, to) {
+                self.roll_back(renamer, index, from)?;
+                return Err(TransactionError::RolledBack {
+                    from: from.clone(),
+                    source,
+                });
+            }
+        }
+
+        for (from, to) in &self.renames {
+            journal
+                .record(from, to)
+                .map_err(TransactionError::Journal)?;
+        }
+
+        Ok(())
+    }
+
+    /// Undo the renames before `done`.
+    fn roll_back(
+        &self,
+        renamer: &Renamer,
+        done: usize,
+        failed: &Utf8Path,
+    ) -> Result<(), TransactionError> {
+        for (from, to) in self.renames[..done].iter().rev() {
+            rename_once(renamer, to, from).map_err(|source| TransactionError::RollbackFailed {
+                failed: failed.to_path_buf(),
+                from: from.clone(),
+                source,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+fn rename_once(renamer: &Renamer, from: &Utf8Path, to: &Utf8Path) -> Result<(), RenameError> {
+    if !from.exists() {
+        return Err(RenameError::SourceMissing {
+            path: from.to_path_buf(),
+        });
+    }
+
+    if is_taken(from, to) {
+        return Err(RenameError::Collision {
+            path: to.to_path_buf(),
+        });
+    }
+
+    renamer.rename_path(from, to)
+}
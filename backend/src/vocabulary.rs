@@ -0,0 +1,153 @@
+//! A controlled vocabulary for tags, to keep a shared archive free of synonyms and typos.
+//!
+//! ```toml
+//! [tags.vocabulary]
+//! allowed = ["invoice", "receipt", "work"]
+//! aliases = { inv = "invoice" }
+//! parents = { invoice = "finance", receipt = "finance" }
+//! ```
+
+use crate::{normalise_text, UnicodeForm};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Allowed tags, their aliases and their parents.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagVocabulary {
+    /// Tags used as parents are allowed too, without being listed here. The order is the one
+    /// of [crate::TagOrder::Vocabulary].
+    pub allowed: Vec<String>,
+    /// Alias to canonical tag.
+    pub aliases: BTreeMap<String, String>,
+    /// Tag to its parent, a name tagged `invoice` is also about `finance`.
+    pub parents: BTreeMap<String, String>,
+}
+
+/// Whether a tag belongs to the vocabulary, see [TagVocabulary::check].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagCheck {
+    Known,
+    /// The tag should be replaced by its canonical form.
+    Alias {
+        canonical: String,
+    },
+    /// The closest known tag, if one is close enough to be a typo.
+    Unknown {
+        closest: Option<String>,
+    },
+}
+
+impl TagVocabulary {
+    /// Check a tag, the vocabulary being compared in the Unicode form, if any.
+    pub fn check(&self, tag: &str, form: Option<UnicodeForm>) -> TagCheck {
+        let tag = normalise_text(form, tag);
+        if self.is_known(&tag, form) {
+            return TagCheck::Known;
+        }
+
+        if let Some((_, canonical)) = self
+            .aliases
+            .iter()
+            .find(|(alias, _)| normalise_text(form, alias) == tag)
+        {
+            return TagCheck::Alias {
+                canonical: canonical.clone(),
+            };
+        }
+
+        TagCheck::Unknown {
+            closest: self.closest(&tag, form),
+        }
+    }
+
+    /// The parent of the tag, then the parent of the parent, and so on.
+    pub fn ancestors<'v>(&'v self, tag: &str, form: Option<UnicodeForm>) -> Vec<&'v str> {
+        let tag = normalise_text(form, tag);
+        let mut ancestors: Vec<&str> = Vec::new();
+        let mut current = tag.clone();
+
+        while let Some(parent) = self.parent(&current, form) {
+            let normalised = normalise_text(form, parent);
+            // A loop in the configuration must not hang us.
+            if normalised == tag
+                || ancestors
+                    .iter()
+                    .any(|ancestor| normalise_text(form, ancestor) == normalised)
+            {
+                break;
+            }
+            ancestors.push(parent);
+            current = normalised;
+        }
+
+        ancestors
+    }
+
+    /// Where the tag is in the allowed tags.
+    pub fn position(&self, tag: &str, form: Option<UnicodeForm>) -> Option<usize> {
+        let tag = normalise_text(form, tag);
+        self.allowed
+            .iter()
+            .position(|allowed| normalise_text(form, allowed) == tag)
+    }
+
+    fn parent(&self, tag: &str, form: Option<UnicodeForm>) -> Option<&str> {
+        self.parents
+            .iter()
+            .find(|(child, _)| normalise_text(form, child) == tag)
+            .map(|(_, parent)| parent.as_str())
+    }
+
+    fn is_known(&self, tag: &str, form: Option<UnicodeForm>) -> bool {
+        self.allowed
+            .iter()
+            .chain(self.parents.keys())
+            .chain(self.parents.values())
+            .any(|known| normalise_text(form, known) == tag)
+    }
+
+    /// Typos are a few edits away from a known tag, or from an alias of one.
+    fn closest(&self, tag: &str, form: Option<UnicodeForm>) -> Option<String> {
+        let known = self
+            .allowed
+            .iter()
+            .chain(self.parents.keys())
+            .chain(self.parents.values())
+            .map(|known| (known, known));
+        let aliases = self.aliases.iter();
+
+        let max_distance = (tag.chars().count() / 3).max(1);
+
+        known
+            .chain(aliases)
+            .map(|(candidate, canonical)| {
+                let distance = levenshtein(tag, &normalise_text(form, candidate));
+                (distance, canonical)
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, canonical)| canonical.clone())
+    }
+}
+
+/// Number of characters to insert, delete or substitute to go from one text to the other.
+fn levenshtein(first: &str, second: &str) -> usize {
+    let second: Vec<char> = second.chars().collect();
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+
+    for (i, first_char) in first.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, second_char) in second.iter().enumerate() {
+            let substitution = previous[j] + usize::from(first_char != *second_char);
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(substitution.min(insertion).min(deletion));
+        }
+
+        previous = current;
+    }
+
+    previous[second.len()]
+}
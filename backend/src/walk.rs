@@ -0,0 +1,204 @@
+//! Find the files/directories to work on in directory trees.
+
+use crate::DIRECTORY_CONFIGURATION_FILE_NAME;
+use camino::{Utf8Path, Utf8PathBuf};
+use glob::Pattern;
+use std::{fs, io, path::PathBuf};
+use thiserror::Error;
+
+/// Which entries of the walked directories are kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WalkTargets {
+    /// The walked directories and their sub-directories.
+    Directories,
+    /// What is inside the directories, except the directories.
+    #[default]
+    Contents,
+    /// Everything, directories included.
+    Both,
+}
+
+/// How to walk the directory trees.
+#[derive(Clone, Debug, Default)]
+pub struct WalkOptions {
+    pub targets: WalkTargets,
+    /// How deep to go, the direct content of a directory is at depth 1. No limit when `None`.
+    pub max_depth: Option<usize>,
+    /// Glob patterns matched on the path relative to the walked directory. When there are
+    /// some, only the paths matching at least one of them are kept.
+    pub include: Vec<String>,
+    /// Glob patterns matched on the path relative to the walked directory. Matching paths are
+    /// neither kept nor walked.
+    pub exclude: Vec<String>,
+    /// Hidden files/directories (starting with a dot) are ignored unless asked.
+    pub hidden: bool,
+}
+
+/// Everything that can go wrong while walking.
+#[derive(Debug, Error)]
+pub enum WalkError {
+    #[error("invalid glob pattern {pattern}")]
+    Pattern {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+    #[error("cannot read {path}")]
+    Read {
+        path: Utf8PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{} is not a valid UTF-8 path", path.display())]
+    NotUtf8 { path: PathBuf },
+}
+
+impl WalkOptions {
+    /// Walk the roots that are directories, roots that are not are kept as is. Paths are sorted
+    /// the deepest first, so renaming them in order never invalidates the next ones.
+    pub fn walk(&self, roots: &[Utf8PathBuf]) -> Result<Vec<Utf8PathBuf>, WalkError> {
+        let walker = Walker {
+            options: self,
+            include: compile(&self.include)?,
+            exclude: compile(&self.exclude)?,
+        };
+
+        let mut found = Vec::new();
+        for root in roots {
+            if root.is_dir() {
+                // Roots without a name, such as `.` or `..`, cannot be renamed.
+                if self.targets != WalkTargets::Contents && root.file_name().is_some() {
+                    found.push((0, root.clone()));
+                }
+                walker.walk_directory(root, root, 1, &mut found)?;
+            } else {
+                found.push((0, root.clone()));
+            }
+        }
+
+        // Overlapping roots find the same paths at several depths, maybe written differently
+        // such as `a` and `./a`. The deepest one is kept.
+        let mut found: Vec<_> = found
+            .into_iter()
+            .map(|(depth, path)| (identity(&path), depth, path))
+            .collect();
+        found.sort_by(|(first, first_depth, _), (second, second_depth, _)| {
+            first
+                .cmp(second)
+                .then_with(|| second_depth.cmp(first_depth))
+        });
+        found.dedup_by(|(path, ..), (kept, ..)| path == kept);
+        let mut found: Vec<_> = found
+            .into_iter()
+            .map(|(_, depth, path)| (depth, path))
+            .collect();
+
+        found.sort_by(|(first_depth, first), (second_depth, second)| {
+            second_depth
+                .cmp(first_depth)
+                .then_with(|| first.cmp(second))
+        });
+
+        Ok(found.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+struct Walker<'o> {
+    options: &'o WalkOptions,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl<'o> Walker<'o> {
+    fn walk_directory(
+        &self,
+        root: &Utf8Path,
+        directory: &Utf8Path,
+        depth: usize,
+        found: &mut Vec<(usize, Utf8PathBuf)>,
+    ) -> Result<(), WalkError> {
+        if self
+            .options
+            .max_depth
+            .is_some_and(|max_depth| depth > max_depth)
+        {
+            return Ok(());
+        }
+
+        let read_error = |source| WalkError::Read {
+            path: directory.to_path_buf(),
+            source,
+        };
+
+        for entry in fs::read_dir(directory).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+            let path = Utf8PathBuf::from_path_buf(entry.path())
+                .map_err(|path| WalkError::NotUtf8 { path })?;
+
+            let name = path.file_name().unwrap_or_default();
+            if name == DIRECTORY_CONFIGURATION_FILE_NAME
+                || (!self.options.hidden && name.starts_with('.'))
+            {
+                continue;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches(relative.as_str()))
+            {
+                continue;
+            }
+
+            let included = self.include.is_empty()
+                || self
+                    .include
+                    .iter()
+                    .any(|pattern| pattern.matches(relative.as_str()));
+
+            // Symbolic links are not followed, to avoid loops.
+            let is_directory = entry.file_type().map_err(read_error)?.is_dir();
+            let kept = match self.options.targets {
+                WalkTargets::Directories => is_directory,
+                WalkTargets::Contents => !is_directory,
+                WalkTargets::Both => true,
+            };
+
+            if kept && included {
+                found.push((depth, path.clone()));
+            }
+
+            if is_directory {
+                self.walk_directory(root, &path, depth + 1, found)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The same path however it is written, `a`, `./a` and `b/../a` are one. Only the parent is
+/// resolved, a symbolic link and its target stay two paths.
+fn identity(path: &Utf8Path) -> PathBuf {
+    let resolved = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if parent.as_str().is_empty() => {
+            fs::canonicalize(".").map(|parent| parent.join(name))
+        }
+        (Some(parent), Some(name)) => fs::canonicalize(parent).map(|parent| parent.join(name)),
+        _ => fs::canonicalize(path),
+    };
+    resolved.unwrap_or_else(|_| path.into())
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>, WalkError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|source| WalkError::Pattern {
+                pattern: pattern.clone(),
+                source,
+            })
+        })
+        .collect()
+}
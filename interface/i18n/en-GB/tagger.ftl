@@ -3,4 +3,54 @@ double-click = Double-click!
 
 tray-tool-name = Tagger
 tray-tag-files = Tag files
-tray-exit = Exit
\ No newline at end of file
+tray-exit = Exit
+
+edit-renamed = { $from } → { $to }
+edit-skipped = { $path } is skipped, its new name is taken.
+edit-metadata = Cannot read the timestamp of { $path }.
+edit-no-embedded-timestamp = No timestamp is embedded in { $path }, it is left without one.
+edit-unknown-tag = The tag "{ $tag }" is not in the vocabulary.
+edit-unknown-tag-closest = The tag "{ $tag }" is not in the vocabulary, did you mean "{ $closest }"?
+edit-unwritable-tag = The tag "{ $tag }" cannot be written in a name, it would not be read back the same.
+
+normalise-unwritable-tag = { $path } is not normalised, its tag "{ $tag }" would not be read back the same.
+
+preview-skipped = skipped
+preview-collision = already exists
+preview-collisions = { $count ->
+    [one] One target would collide with an existing file.
+   *[other] { $count } targets would collide with existing files.
+}
+
+undo-nothing = Nothing to undo.
+undo-session = { $session }: { $count ->
+    [one] one rename
+   *[other] { $count } renames
+}, { $time }
+
+list-header-timestamp = Timestamp
+list-header-name = Name
+list-header-tags = Tags
+list-header-path = Path
+
+tags-header-tag = Tag
+tags-header-count = Uses
+tags-header-first = First
+tags-header-last = Last
+tags-header-directories = Directories
+tags-header-values = Values
+
+tags-merge-usage = Expected the tags to merge, INTO, then the tag replacing them.
+tags-nothing-to-rename = No name has these tags.
+tags-renamed = { $count ->
+    [one] One file renamed.
+   *[other] { $count } files renamed.
+}
+
+lint-unknown = { $path }: "{ $tag }" is not in the vocabulary
+lint-unknown-closest = { $path }: "{ $tag }" is not in the vocabulary, did you mean "{ $closest }"?
+lint-alias = { $path }: "{ $tag }" is an alias of "{ $canonical }"
+lint-issues = { $count ->
+    [one] One tag does not follow the vocabulary.
+   *[other] { $count } tags do not follow the vocabulary.
+}
@@ -3,4 +3,54 @@ double-click = Double clic !
 
 tray-tool-name = Tagger
 tray-tag-files = Tagger des fichiers
-tray-exit = Quitter
\ No newline at end of file
+tray-exit = Quitter
+
+edit-renamed = { $from } → { $to }
+edit-skipped = { $path } est ignoré, son nouveau nom est déjà pris.
+edit-metadata = Impossible de lire l'horodatage de { $path }.
+edit-no-embedded-timestamp = Aucun horodatage n'est intégré à { $path }, il reste sans horodatage.
+edit-unknown-tag = Le tag « { $tag } » n'est pas dans le vocabulaire.
+edit-unknown-tag-closest = Le tag « { $tag } » n'est pas dans le vocabulaire, vouliez-vous dire « { $closest } » ?
+edit-unwritable-tag = Le tag « { $tag } » ne peut pas être écrit dans un nom, il ne serait pas relu tel quel.
+
+normalise-unwritable-tag = { $path } n'est pas normalisé, son tag « { $tag } » ne serait pas relu tel quel.
+
+preview-skipped = ignoré
+preview-collision = existe déjà
+preview-collisions = { $count ->
+    [one] Une cible entrerait en conflit avec un fichier existant.
+   *[other] { $count } cibles entreraient en conflit avec des fichiers existants.
+}
+
+undo-nothing = Rien à annuler.
+undo-session = { $session } : { $count ->
+    [one] un renommage
+   *[other] { $count } renommages
+}, { $time }
+
+list-header-timestamp = Horodatage
+list-header-name = Nom
+list-header-tags = Tags
+list-header-path = Chemin
+
+tags-header-tag = Tag
+tags-header-count = Utilisations
+tags-header-first = Premier
+tags-header-last = Dernier
+tags-header-directories = Répertoires
+tags-header-values = Valeurs
+
+tags-merge-usage = Attendu : les tags à fusionner, INTO, puis le tag qui les remplace.
+tags-nothing-to-rename = Aucun nom n'a ces tags.
+tags-renamed = { $count ->
+    [one] Un fichier renommé.
+   *[other] { $count } fichiers renommés.
+}
+
+lint-unknown = { $path } : « { $tag } » n'est pas dans le vocabulaire
+lint-unknown-closest = { $path } : « { $tag } » n'est pas dans le vocabulaire, vouliez-vous dire « { $closest } » ?
+lint-alias = { $path } : « { $tag } » est un alias de « { $canonical } »
+lint-issues = { $count ->
+    [one] Un tag ne respecte pas le vocabulaire.
+   *[other] { $count } tags ne respectent pas le vocabulaire.
+}
@@ -1,4 +1,11 @@
-use clap::{Parser, Subcommand};
+use backend::{
+    CollisionStrategy, TagExpression, TagOrder, TimestampSource, WalkError, WalkOptions,
+    WalkTargets,
+};
+use camino::Utf8PathBuf;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(name = "tagger")]
@@ -17,30 +24,33 @@ pub enum TaggerCommand {
     ///  * https://github.com/novoid/appendfilename
     ///  * https://github.com/novoid/date2name
     #[clap(verbatim_doc_comment)]
-    Edit {
-        /// Add text after the date and before the file name
-        #[arg(short = 'p', long)]
-        prefix: Option<String>,
-        /// Add text after the file name and before tag separator (-- by default)
+    Edit(EditArgs),
+    /// To rename files back, as they were before tagger renamed them.
+    ///
+    /// Without option, undo every rename of the last run.
+    Undo {
+        /// Undo the given number of renames, the most recent first
+        #[arg(short = 'l', long, conflicts_with = "session")]
+        last: Option<usize>,
+        /// Undo every rename of a given session
         #[arg(short = 's', long)]
-        suffix: Option<String>,
-        /// Add a tag after the tag separator (-- by default)
-        #[arg(short = 'a', long)]
-        add_tag: Vec<String>,
-        /// Remove a tag after the tag separator (-- by default) if it's there
-        #[arg(short = 'r', long)]
-        remove_tag: Vec<String>,
-        /// Add the date (without time) before the filename
-        #[arg(short = 'd', long)]
-        add_date: bool,
-        /// Add the date and the time before the filename
-        #[arg(short = 't', long)]
-        add_datetime: bool,
-        /// Add the time (without the date) before the filename
-        #[arg(long)]
-        add_time: bool,
-        /// The files/folders to edit.
-        targets_path: Vec<String>,
+        session: Option<String>,
+        /// List the sessions that can be undone instead
+        #[arg(long, conflicts_with_all = ["last", "session"])]
+        list: bool,
+    },
+    /// To rewrite timestamps and tag separators with the main formats of the configuration,
+    /// remove duplicate and empty tags, and sort tags.
+    Normalise(NormaliseArgs),
+    /// To find files and directories by tags, dates and names, without changing anything.
+    List(ListArgs),
+    /// To see every tag in use, how often and where, to spot typos and synonyms. Or to rename
+    /// and merge tags across whole trees.
+    Tags(TagsArgs),
+    /// To find the tags that are not in the vocabulary, or that are aliases of other tags.
+    Lint {
+        #[command(flatten)]
+        tree: TreeArgs,
     },
     /// To maintain a background presence and get global shortcuts up and running.
     Daemon {
@@ -48,6 +58,329 @@ pub enum TaggerCommand {
     },
 }
 
+#[derive(Debug, Args)]
+pub struct EditArgs {
+    /// Add text after the date and before the file name
+    #[arg(short = 'p', long)]
+    pub prefix: Option<String>,
+    /// Add text after the file name and before tag separator (-- by default)
+    #[arg(short = 's', long)]
+    pub suffix: Option<String>,
+    /// Add a tag after the tag separator (-- by default)
+    #[arg(short = 'a', long)]
+    pub add_tag: Vec<String>,
+    /// Remove a tag after the tag separator (-- by default) if it's there
+    #[arg(short = 'r', long)]
+    pub remove_tag: Vec<String>,
+    /// Set a key/value tag such as status=final, replacing the tags with the same key
+    #[arg(long)]
+    pub set_tag: Vec<String>,
+    /// Add the date (without time) before the filename
+    #[arg(short = 'd', long, conflicts_with_all = ["add_datetime", "add_time"])]
+    pub add_date: bool,
+    /// Add the date and the time before the filename
+    #[arg(short = 't', long, conflicts_with = "add_time")]
+    pub add_datetime: bool,
+    /// Add the time (without the date) before the filename
+    #[arg(long)]
+    pub add_time: bool,
+    /// Where the added date or time comes from
+    #[arg(long, value_enum, default_value_t = TimestampFrom::Now)]
+    pub timestamp_from: TimestampFrom,
+    /// Only show what would be renamed, fails if a target would collide with an existing file
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+    /// What to do when the new name is already taken
+    #[arg(long, value_enum, default_value_t = Collision::Fail)]
+    pub on_collision: Collision,
+    /// Allow moving across devices, which copies then deletes instead of renaming
+    #[arg(long)]
+    pub cross_device: bool,
+    #[command(flatten)]
+    pub walk: WalkArgs,
+    /// The files/folders to edit.
+    pub targets_path: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct NormaliseArgs {
+    /// Only show what would be renamed, fails if a target would collide with an existing file
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+    /// What to do when the new name is already taken
+    #[arg(long, value_enum, default_value_t = Collision::Fail)]
+    pub on_collision: Collision,
+    /// Allow moving across devices, which copies then deletes instead of renaming
+    #[arg(long)]
+    pub cross_device: bool,
+    /// How to sort the tags, instead of the order of the configuration
+    #[arg(long, value_enum)]
+    pub tag_order: Option<Order>,
+    /// Render the date-times in this timezone, such as UTC or Europe/Paris
+    #[arg(long)]
+    pub convert_tz: Option<Tz>,
+    #[command(flatten)]
+    pub tree: TreeArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// Keep the names with these tags, such as "work AND NOT (draft OR old)"
+    #[arg(short = 't', long)]
+    pub tags: Option<TagExpression>,
+    /// Keep the names with a timestamp on this day or after, such as 2022-01-01
+    #[arg(long)]
+    pub since: Option<NaiveDate>,
+    /// Keep the names with a timestamp on this day or before, such as 2022-12-31
+    #[arg(long)]
+    pub until: Option<NaiveDate>,
+    /// Keep the names containing this text, ignoring the case
+    #[arg(short = 'n', long)]
+    pub name: Option<String>,
+    /// How to show the results
+    #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+    #[command(flatten)]
+    pub tree: TreeArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct TagsArgs {
+    #[command(subcommand)]
+    pub command: Option<TagsCommand>,
+    /// How to show the results
+    #[arg(short = 'f', long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+    #[command(flatten)]
+    pub tree: TreeArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagsCommand {
+    /// To replace a tag by another one in every name, such as `rename inv invoice`.
+    Rename {
+        old: String,
+        new: String,
+        /// Only show what would be renamed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        #[command(flatten)]
+        tree: TreeArgs,
+    },
+    /// To replace many tags by a single one in every name, such as `merge inv bill INTO invoice`.
+    Merge {
+        /// The tags to replace, INTO, the tag replacing them, then the files/folders to go
+        /// through (the current directory by default)
+        #[arg(required = true, value_name = "TAGS INTO TAG")]
+        arguments: Vec<String>,
+        /// Only show what would be renamed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        #[command(flatten)]
+        tree: TreeOptions,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OutputFormat {
+    /// One result per line
+    Plain,
+    /// Aligned columns
+    Table,
+    /// An array of objects
+    Json,
+}
+
+/// To go through whole directory trees, for the commands that always do.
+#[derive(Debug, Args)]
+pub struct TreeArgs {
+    #[command(flatten)]
+    pub options: TreeOptions,
+    /// The files/folders to go through
+    #[arg(default_value = ".")]
+    pub paths: Vec<String>,
+}
+
+impl TreeArgs {
+    /// Every path found, the deepest first.
+    pub fn walk(&self) -> Result<Vec<Utf8PathBuf>, WalkError> {
+        self.options.walk(&self.paths)
+    }
+}
+
+/// How to go through the directory trees, without the paths themselves.
+#[derive(Debug, Args)]
+pub struct TreeOptions {
+    /// Only keep the paths matching this glob pattern, relative to the given directory
+    #[arg(long)]
+    pub include: Vec<String>,
+    /// Ignore the paths matching this glob pattern, relative to the given directory
+    #[arg(long)]
+    pub exclude: Vec<String>,
+    /// How deep to go, 1 being the direct content of the given directories
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+    /// Work on the directories themselves, their contents or both
+    #[arg(long, value_enum, default_value_t = ApplyTo::Contents)]
+    pub apply_to: ApplyTo,
+    /// Do not ignore hidden files and directories
+    #[arg(long)]
+    pub hidden: bool,
+}
+
+impl TreeOptions {
+    /// Every path found in the given paths, the deepest first.
+    pub fn walk(&self, paths: &[String]) -> Result<Vec<Utf8PathBuf>, WalkError> {
+        let paths: Vec<_> = paths.iter().map(Utf8PathBuf::from).collect();
+
+        WalkOptions {
+            targets: self.apply_to.into(),
+            max_depth: self.max_depth,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            hidden: self.hidden,
+        }
+        .walk(&paths)
+    }
+}
+
+/// To go through whole directory trees instead of the given paths only.
+#[derive(Debug, Args)]
+pub struct WalkArgs {
+    /// Go through the content of the given directories
+    #[arg(short = 'R', long)]
+    pub recursive: bool,
+    /// Only keep the paths matching this glob pattern, relative to the given directory
+    #[arg(long, requires = "recursive")]
+    pub include: Vec<String>,
+    /// Ignore the paths matching this glob pattern, relative to the given directory
+    #[arg(long, requires = "recursive")]
+    pub exclude: Vec<String>,
+    /// How deep to go, 1 being the direct content of the given directories
+    #[arg(long, requires = "recursive")]
+    pub max_depth: Option<usize>,
+    /// Work on the directories themselves, their contents or both
+    #[arg(long, value_enum, default_value_t = ApplyTo::Contents, requires = "recursive")]
+    pub apply_to: ApplyTo,
+    /// Do not ignore hidden files and directories
+    #[arg(long, requires = "recursive")]
+    pub hidden: bool,
+}
+
+impl WalkArgs {
+    /// The given paths, walked if asked, the deepest first.
+    pub fn targets(&self, paths: &[String]) -> Result<Vec<Utf8PathBuf>, WalkError> {
+        let paths: Vec<_> = paths.iter().map(Utf8PathBuf::from).collect();
+
+        if !self.recursive {
+            return Ok(paths);
+        }
+
+        WalkOptions {
+            targets: self.apply_to.into(),
+            max_depth: self.max_depth,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            hidden: self.hidden,
+        }
+        .walk(&paths)
+    }
+}
+
+/// Mirrors [WalkTargets] for the command line.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ApplyTo {
+    /// The directories and their sub-directories
+    Directories,
+    /// The files inside the directories
+    Contents,
+    /// Both of them
+    Both,
+}
+
+impl From<ApplyTo> for WalkTargets {
+    fn from(apply_to: ApplyTo) -> Self {
+        match apply_to {
+            ApplyTo::Directories => WalkTargets::Directories,
+            ApplyTo::Contents => WalkTargets::Contents,
+            ApplyTo::Both => WalkTargets::Both,
+        }
+    }
+}
+
+/// Mirrors [CollisionStrategy] for the command line.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Collision {
+    /// Stop with an error
+    Fail,
+    /// Leave the file as is
+    Skip,
+    /// Append a counter after the file name, before the tags
+    Counter,
+    /// Replace the existing file
+    Overwrite,
+}
+
+impl From<Collision> for CollisionStrategy {
+    fn from(collision: Collision) -> Self {
+        match collision {
+            Collision::Fail => CollisionStrategy::Fail,
+            Collision::Skip => CollisionStrategy::Skip,
+            Collision::Counter => CollisionStrategy::Counter,
+            Collision::Overwrite => CollisionStrategy::Overwrite,
+        }
+    }
+}
+
+/// Mirrors [TimestampSource] for the command line.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TimestampFrom {
+    /// The current time
+    Now,
+    /// The last modification of the file
+    Modified,
+    /// The creation of the file, when the filesystem keeps it
+    Created,
+    /// The oldest of the modification and the creation
+    Oldest,
+    /// The metadata inside the file: EXIF of photos, creation of videos and PDFs
+    Embedded,
+}
+
+impl From<TimestampFrom> for TimestampSource {
+    fn from(timestamp_from: TimestampFrom) -> Self {
+        match timestamp_from {
+            TimestampFrom::Now => TimestampSource::Now,
+            TimestampFrom::Modified => TimestampSource::Modified,
+            TimestampFrom::Created => TimestampSource::Created,
+            TimestampFrom::Oldest => TimestampSource::Oldest,
+            TimestampFrom::Embedded => TimestampSource::Embedded,
+        }
+    }
+}
+
+/// Mirrors [TagOrder] for the command line.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Order {
+    /// As they are in the name
+    Keep,
+    /// Ignoring the case
+    Alphabetical,
+    /// In the order of the vocabulary, the others last
+    Vocabulary,
+}
+
+impl From<Order> for TagOrder {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Keep => TagOrder::Keep,
+            Order::Alphabetical => TagOrder::Alphabetical,
+            Order::Vocabulary => TagOrder::Vocabulary,
+        }
+    }
+}
+
 pub fn parse() -> TaggerCli {
     // Wild allows us to get filename expansion on Windows too.
     let args = wild::args();
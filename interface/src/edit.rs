@@ -0,0 +1,215 @@
+use crate::{
+    cli::EditArgs,
+    i18n::fl,
+    preview::{PreviewStatus, PreviewTable},
+};
+use anyhow::{bail, Context};
+use backend::{
+    ConfigurationCache, Extractors, Journal, RenameError, RenameOutcome, Renamer,
+    StructuredNameConfiguration, StructuredPath, TagCheck, TagConfiguration, TimestampKind,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use log::debug;
+use std::collections::HashSet;
+
+/// Apply the requested changes to every target then rename them on the disk.
+pub fn run(args: &EditArgs) -> anyhow::Result<()> {
+    let renamer = Renamer {
+        collision: args.on_collision.into(),
+        allow_cross_device: args.cross_device,
+    };
+
+    let targets = args.walk.targets(&args.targets_path)?;
+
+    // Unknown tags stop everything before the first rename.
+    let configurations = ConfigurationCache::load_all(&targets)?;
+    for target in &targets {
+        if let Some(configuration) = configurations.get(target) {
+            for tag in args.add_tag.iter().chain(&args.set_tag) {
+                resolve_tag(&configuration.tag_configuration, tag)?;
+            }
+        }
+    }
+
+    let edit = |path: &mut StructuredPath| apply_edits(path, args);
+
+    if args.dry_run {
+        preview(&targets, &renamer, edit)
+    } else {
+        rename_targets(&targets, &renamer, edit)
+    }
+}
+
+/// Show every rename without touching anything. Fails when a target would collide with an
+/// existing file or with another renamed target.
+pub fn preview(
+    targets: &[Utf8PathBuf],
+    renamer: &Renamer,
+    edit: impl Fn(&mut StructuredPath) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut table = PreviewTable::default();
+    let mut new_paths = HashSet::new();
+
+    for path in targets {
+        let configuration = StructuredNameConfiguration::load_for(path)?;
+        let original = StructuredPath::parse_from(path, &configuration);
+        let mut edited = original.clone();
+        edit(&mut edited)?;
+
+        match renamer.plan(&edited) {
+            Ok(RenameOutcome::Unchanged) => {
+                table.push(&original, &edited, PreviewStatus::Unchanged)
+            }
+            Ok(RenameOutcome::Skipped) => table.push(&original, &edited, PreviewStatus::Skipped),
+            Ok(RenameOutcome::Renamed(renamed)) => {
+                let status = if new_paths.insert(Utf8PathBuf::from(renamed.to_string())) {
+                    PreviewStatus::Renamed
+                } else {
+                    PreviewStatus::Collision
+                };
+                table.push(&original, &renamed, status);
+            }
+            Err(RenameError::Collision { .. }) => {
+                table.push(&original, &edited, PreviewStatus::Collision)
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    table.print()?;
+
+    let collisions = table.collisions();
+    if collisions > 0 {
+        bail!(fl!("preview-collisions", count = collisions));
+    }
+
+    Ok(())
+}
+
+/// Edit then rename the targets one after the other.
+pub fn rename_targets(
+    targets: &[Utf8PathBuf],
+    renamer: &Renamer,
+    edit: impl Fn(&mut StructuredPath) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    // Renames are recorded so they can be undone.
+    let journal = Journal::open_default()?;
+
+    for target in targets {
+        edit_target(target, renamer, &journal, &edit)?;
+    }
+
+    Ok(())
+}
+
+fn edit_target(
+    path: &Utf8Path,
+    renamer: &Renamer,
+    journal: &Journal,
+    edit: impl Fn(&mut StructuredPath) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    // Each target may have its own `.tagger.toml` in its parent directories.
+    let configuration = StructuredNameConfiguration::load_for(path)?;
+    let mut structured_path = StructuredPath::parse_from(path, &configuration);
+    debug!("{:?}", structured_path);
+
+    edit(&mut structured_path)?;
+
+    match renamer.rename(&structured_path)? {
+        RenameOutcome::Unchanged => debug!("{} is left unchanged", path),
+        RenameOutcome::Skipped => println!("{}", fl!("edit-skipped", path = path.as_str())),
+        RenameOutcome::Renamed(renamed) => {
+            let new_path = Utf8PathBuf::from(renamed.to_string());
+            journal.record(path, &new_path)?;
+
+            println!(
+                "{}",
+                fl!("edit-renamed", from = path.as_str(), to = new_path.as_str())
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_edits(path: &mut StructuredPath, args: &EditArgs) -> anyhow::Result<()> {
+    let name = &mut path.name;
+
+    // An existing timestamp is kept as is, we only add one when there is none.
+    let kind = if args.add_datetime {
+        Some(TimestampKind::DateTime)
+    } else if args.add_date {
+        Some(TimestampKind::Date)
+    } else if args.add_time {
+        Some(TimestampKind::Time)
+    } else {
+        None
+    };
+
+    if let (None, Some(kind)) = (&name.timestamp, kind) {
+        let timestamp = name
+            .configuration
+            .timestamp_configuration
+            .timestamp_for(
+                path.original_path,
+                args.timestamp_from.into(),
+                kind,
+                &Extractors::default(),
+            )
+            .with_context(|| fl!("edit-metadata", path = path.original_path.as_str()))?;
+
+        match timestamp {
+            Some(timestamp) => name.set_timestamp(timestamp.value),
+            // The other edits still apply.
+            None => eprintln!(
+                "{}",
+                fl!(
+                    "edit-no-embedded-timestamp",
+                    path = path.original_path.as_str()
+                )
+            ),
+        }
+    }
+
+    if let Some(prefix) = &args.prefix {
+        name.push_prefix(prefix);
+    }
+
+    if let Some(suffix) = &args.suffix {
+        name.push_suffix(suffix);
+    }
+
+    for tag in &args.remove_tag {
+        name.remove_tag(tag);
+    }
+
+    for tag in &args.add_tag {
+        let tag = resolve_tag(&name.configuration.tag_configuration, tag)?;
+        name.add_tag(&tag)
+            .with_context(|| fl!("edit-unwritable-tag", tag = tag.as_str()))?;
+    }
+
+    for tag in &args.set_tag {
+        let tag = resolve_tag(&name.configuration.tag_configuration, tag)?;
+        name.set_tag(&tag)
+            .with_context(|| fl!("edit-unwritable-tag", tag = tag.as_str()))?;
+    }
+
+    Ok(())
+}
+
+/// The tag to add according to the vocabulary: aliases are replaced by their canonical tag.
+pub fn resolve_tag(configuration: &TagConfiguration, tag: &str) -> anyhow::Result<String> {
+    match configuration.check_tag(tag) {
+        TagCheck::Known => Ok(tag.to_string()),
+        TagCheck::Alias { canonical } => Ok(canonical),
+        TagCheck::Unknown {
+            closest: Some(closest),
+        } => bail!(fl!(
+            "edit-unknown-tag-closest",
+            tag = tag,
+            closest = closest.as_str()
+        )),
+        TagCheck::Unknown { closest: None } => bail!(fl!("edit-unknown-tag", tag = tag)),
+    }
+}
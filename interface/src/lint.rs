@@ -0,0 +1,53 @@
+use crate::{cli::TreeArgs, i18n::fl};
+use anyhow::bail;
+use backend::{ConfigurationCache, StructuredPath, TagCheck};
+
+/// Report every tag outside of the vocabulary, fails if there is any.
+pub fn run(tree: &TreeArgs) -> anyhow::Result<()> {
+    let mut paths = tree.walk()?;
+    paths.sort();
+    let configurations = ConfigurationCache::load_all(&paths)?;
+
+    let mut issues = 0;
+    for path in &paths {
+        let Some(configuration) = configurations.get(path) else {
+            continue;
+        };
+        let structured_path = StructuredPath::parse_from(path, configuration);
+        let Some(tags) = &structured_path.name.tags else {
+            continue;
+        };
+
+        for tag in tags.iter() {
+            let message = match configuration.tag_configuration.check_tag(tag) {
+                TagCheck::Known => continue,
+                TagCheck::Alias { canonical } => fl!(
+                    "lint-alias",
+                    path = path.as_str(),
+                    tag = tag,
+                    canonical = canonical.as_str()
+                ),
+                TagCheck::Unknown {
+                    closest: Some(closest),
+                } => fl!(
+                    "lint-unknown-closest",
+                    path = path.as_str(),
+                    tag = tag,
+                    closest = closest.as_str()
+                ),
+                TagCheck::Unknown { closest: None } => {
+                    fl!("lint-unknown", path = path.as_str(), tag = tag)
+                }
+            };
+
+            println!("{}", message);
+            issues += 1;
+        }
+    }
+
+    if issues > 0 {
+        bail!(fl!("lint-issues", count = issues));
+    }
+
+    Ok(())
+}
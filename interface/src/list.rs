@@ -0,0 +1,96 @@
+use crate::{
+    cli::{ListArgs, OutputFormat},
+    i18n::fl,
+    table,
+};
+use backend::{ConfigurationCache, Query, StructuredPath};
+use serde_json::json;
+
+/// Print the paths whose parsed names match the query.
+pub fn run(args: &ListArgs) -> anyhow::Result<()> {
+    let mut paths = args.tree.walk()?;
+    paths.sort();
+    let configurations = ConfigurationCache::load_all(&paths)?;
+
+    let query = Query {
+        tags: args.tags.clone(),
+        since: args.since,
+        until: args.until,
+        name: args.name.clone(),
+    };
+
+    let matching: Vec<_> = paths
+        .iter()
+        .filter_map(|path| {
+            let structured_path = StructuredPath::parse_from(path, configurations.get(path)?);
+            query
+                .matches(&structured_path.name)
+                .then_some(structured_path)
+        })
+        .collect();
+
+    match args.format {
+        OutputFormat::Plain => {
+            for structured_path in &matching {
+                println!("{}", structured_path.original_path);
+            }
+        }
+        OutputFormat::Table => {
+            let headers = [
+                fl!("list-header-timestamp"),
+                fl!("list-header-name"),
+                fl!("list-header-tags"),
+                fl!("list-header-path"),
+            ];
+            let rows: Vec<_> = matching
+                .iter()
+                .map(|structured_path| {
+                    let name = &structured_path.name;
+                    vec![
+                        name.timestamp
+                            .as_ref()
+                            .map(ToString::to_string)
+                            .unwrap_or_default(),
+                        name.filename
+                            .as_deref()
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string(),
+                        tags(structured_path).join(" "),
+                        structured_path.original_path.to_string(),
+                    ]
+                })
+                .collect();
+
+            table::print(&headers, &rows);
+        }
+        OutputFormat::Json => {
+            let objects: Vec<_> = matching
+                .iter()
+                .map(|structured_path| {
+                    let name = &structured_path.name;
+                    json!({
+                        "path": structured_path.original_path,
+                        "timestamp": name.timestamp.as_ref().map(|timestamp| timestamp.value),
+                        "name": name.filename.as_deref().map(str::trim),
+                        "tags": tags(structured_path),
+                        "extension": name.extension,
+                    })
+                })
+                .collect();
+
+            println!("{}", serde_json::to_string_pretty(&objects)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn tags<'a>(structured_path: &'a StructuredPath) -> Vec<&'a str> {
+    structured_path
+        .name
+        .tags
+        .iter()
+        .flat_map(|tags| tags.iter())
+        .collect()
+}
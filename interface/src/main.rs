@@ -6,17 +6,44 @@ use tray_icon::TaggerTrayEvent;
 use winit::event_loop::EventLoopBuilder;
 
 mod cli;
+mod edit;
 mod i18n;
+mod lint;
+mod list;
+mod normalise;
+mod preview;
+mod table;
+mod tags;
 mod tray_icon;
+mod undo;
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     env_logger::init();
 
     let args = cli::parse();
+    debug!("{:?}", args);
 
     match args.command {
-        TaggerCommand::Edit { .. } => {
-            debug!("{:?}", args);
+        TaggerCommand::Edit(edit_args) => {
+            edit::run(&edit_args)?;
+        }
+        TaggerCommand::Normalise(normalise_args) => {
+            normalise::run(&normalise_args)?;
+        }
+        TaggerCommand::List(list_args) => {
+            list::run(&list_args)?;
+        }
+        TaggerCommand::Lint { tree } => {
+            lint::run(&tree)?;
+        }
+        TaggerCommand::Tags(tags_args) => {
+            tags::run(&tags_args)?;
+        }
+        TaggerCommand::Undo { list: true, .. } => {
+            undo::list()?;
+        }
+        TaggerCommand::Undo { last, session, .. } => {
+            undo::run(last, session)?;
         }
         // User can use this command to pop up a window allowing drag'n'droping
         // the files to edit. This command starts the tray icon, binds global
@@ -56,4 +83,6 @@ fn main() {
                 .unwrap();
         }
     };
+
+    Ok(())
 }
@@ -0,0 +1,47 @@
+use crate::{
+    cli::NormaliseArgs,
+    edit::{preview, rename_targets},
+    i18n::fl,
+};
+use backend::{Renamer, StructuredPath, TagError};
+
+/// Rename every path of the trees to its normalised name.
+pub fn run(args: &NormaliseArgs) -> anyhow::Result<()> {
+    let renamer = Renamer {
+        collision: args.on_collision.into(),
+        allow_cross_device: args.cross_device,
+    };
+
+    let targets = args.tree.walk()?;
+    let normalise = |path: &mut StructuredPath| {
+        let name = &mut path.name;
+        let normalised = match args.tag_order {
+            Some(order) => name.normalise_with_order(order.into()),
+            None => name.normalise(),
+        };
+
+        // The name is left as it is, the other paths are still normalised.
+        if let Err(TagError::Unwritable { tag }) = normalised {
+            eprintln!(
+                "{}",
+                fl!(
+                    "normalise-unwritable-tag",
+                    path = path.original_path.as_str(),
+                    tag = tag
+                )
+            );
+        }
+
+        if let Some(timezone) = args.convert_tz {
+            name.convert_timezone(timezone);
+        }
+
+        Ok(())
+    };
+
+    if args.dry_run {
+        preview(&targets, &renamer, normalise)
+    } else {
+        rename_targets(&targets, &renamer, normalise)
+    }
+}
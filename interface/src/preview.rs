@@ -0,0 +1,113 @@
+use crate::i18n::fl;
+use anstyle::{AnsiColor, Color, Style};
+use backend::StructuredPath;
+use std::io::{self, Write};
+
+const DIRECTORY_STYLE: Style = Style::new().dimmed();
+const TIMESTAMP_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Blue)));
+const FILENAME_STYLE: Style = Style::new().bold();
+const TAGS_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Green)));
+const EXTENSION_STYLE: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Yellow)));
+const SKIPPED_STYLE: Style = Style::new().italic();
+const COLLISION_STYLE: Style = Style::new()
+    .fg_color(Some(Color::Ansi(AnsiColor::Red)))
+    .bold();
+
+/// What happens to a row of the table.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PreviewStatus {
+    Unchanged,
+    Renamed,
+    Skipped,
+    Collision,
+}
+
+/// An old → new table of renames, coloured part by part.
+#[derive(Default)]
+pub struct PreviewTable {
+    rows: Vec<PreviewRow>,
+}
+
+struct PreviewRow {
+    old: String,
+    old_width: usize,
+    new: String,
+    status: PreviewStatus,
+}
+
+impl PreviewTable {
+    pub fn push(&mut self, old: &StructuredPath, new: &StructuredPath, status: PreviewStatus) {
+        self.rows.push(PreviewRow {
+            old: styled(old),
+            old_width: old.to_string().chars().count(),
+            new: styled(new),
+            status,
+        });
+    }
+
+    pub fn collisions(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|row| row.status == PreviewStatus::Collision)
+            .count()
+    }
+
+    /// Colours are stripped by `anstream` when the output is not a terminal.
+    pub fn print(&self) -> io::Result<()> {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.old_width)
+            .max()
+            .unwrap_or_default();
+
+        let mut stdout = anstream::stdout().lock();
+        for row in &self.rows {
+            let padding = " ".repeat(width - row.old_width);
+            let status = match row.status {
+                PreviewStatus::Unchanged | PreviewStatus::Renamed => String::new(),
+                PreviewStatus::Skipped => {
+                    format!("  {}", paint(SKIPPED_STYLE, &fl!("preview-skipped")))
+                }
+                PreviewStatus::Collision => {
+                    format!("  {}", paint(COLLISION_STYLE, &fl!("preview-collision")))
+                }
+            };
+            writeln!(stdout, "{}{} → {}{}", row.old, padding, row.new, status)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Same output as the `Display` of `StructuredPath`, with a style for each part.
+fn styled(path: &StructuredPath) -> String {
+    let name = &path.name;
+    let full = path.to_string();
+    // `Display` ends with the name, what comes before is the directory.
+    let directory = &full[..full.len() - name.to_string().len()];
+
+    let mut result = paint(DIRECTORY_STYLE, directory);
+    if let Some(timestamp) = &name.timestamp {
+        result.push_str(&paint(TIMESTAMP_STYLE, &timestamp.to_string()));
+    }
+    if let Some(filename) = &name.filename {
+        result.push_str(&paint(FILENAME_STYLE, filename));
+    }
+    if let Some(tags) = &name.tags {
+        result.push_str(&paint(TAGS_STYLE, &tags.to_string()));
+    }
+    if let Some(extension) = name.extension {
+        result.push_str(&paint(EXTENSION_STYLE, &format!(".{}", extension)));
+    }
+
+    result
+}
+
+fn paint(style: Style, text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    format!("{}{}{}", style.render(), text, style.render_reset())
+}
@@ -0,0 +1,30 @@
+/// Print aligned columns, the last one is not padded.
+pub fn print(headers: &[String], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    print_row(headers, &widths);
+    for row in rows {
+        print_row(row, &widths);
+    }
+}
+
+fn print_row(cells: &[String], widths: &[usize]) {
+    let mut line = String::new();
+
+    for (index, (cell, width)) in cells.iter().zip(widths).enumerate() {
+        line.push_str(cell);
+        if index + 1 < cells.len() {
+            line.push_str(&" ".repeat(width - cell.chars().count() + 2));
+        }
+    }
+
+    println!("{}", line);
+}
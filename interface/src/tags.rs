@@ -0,0 +1,209 @@
+use crate::{
+    cli::{OutputFormat, TagsArgs, TagsCommand, TreeArgs},
+    edit::resolve_tag,
+    i18n::fl,
+    preview::{PreviewStatus, PreviewTable},
+    table,
+};
+use anyhow::{bail, Context};
+use backend::{
+    ConfigurationCache, Journal, RenameError, RenameOutcome, RenameTransaction, Renamer,
+    StructuredPath, TagReport, TimestampValue,
+};
+use camino::Utf8PathBuf;
+use chrono::Datelike;
+use std::collections::HashSet;
+
+pub fn run(args: &TagsArgs) -> anyhow::Result<()> {
+    match &args.command {
+        None => report(args.format, &args.tree),
+        Some(TagsCommand::Rename {
+            old,
+            new,
+            dry_run,
+            tree,
+        }) => rewrite_tags(&[old.as_str()], new, &tree.walk()?, *dry_run),
+        Some(TagsCommand::Merge {
+            arguments,
+            dry_run,
+            tree,
+        }) => {
+            // TAGS INTO TAG [PATHS]
+            let Some(into_index) = arguments.iter().position(|argument| argument == "INTO") else {
+                bail!(fl!("tags-merge-usage"));
+            };
+            let (tags, rest) = arguments.split_at(into_index);
+            let (into, paths) = match &rest[1..] {
+                [into, paths @ ..] if !tags.is_empty() => (into, paths),
+                _ => bail!(fl!("tags-merge-usage")),
+            };
+
+            let paths = if paths.is_empty() {
+                tree.walk(&[".".to_string()])?
+            } else {
+                tree.walk(paths)?
+            };
+            let tags: Vec<_> = tags.iter().map(String::as_str).collect();
+            rewrite_tags(&tags, into, &paths, *dry_run)
+        }
+    }
+}
+
+/// Report every tag found in the trees.
+fn report(format: OutputFormat, tree: &TreeArgs) -> anyhow::Result<()> {
+    let paths = tree.walk()?;
+    let configurations = ConfigurationCache::load_all(&paths)?;
+
+    let mut report = TagReport::default();
+    for path in &paths {
+        if let Some(configuration) = configurations.get(path) {
+            report.add(&StructuredPath::parse_from(path, configuration));
+        }
+    }
+
+    match format {
+        OutputFormat::Plain => {
+            for (tag, statistics) in &report.tags {
+                println!("{} ({})", tag, statistics.count);
+                for (value, count) in &statistics.values {
+                    println!("    {} ({})", value, count);
+                }
+            }
+        }
+        OutputFormat::Table => {
+            let headers = [
+                fl!("tags-header-tag"),
+                fl!("tags-header-count"),
+                fl!("tags-header-first"),
+                fl!("tags-header-last"),
+                fl!("tags-header-directories"),
+                fl!("tags-header-values"),
+            ];
+            let rows: Vec<_> = report
+                .tags
+                .iter()
+                .map(|(tag, statistics)| {
+                    let directories: Vec<_> = statistics
+                        .directories
+                        .iter()
+                        .map(|directory| directory.as_str())
+                        .collect();
+                    let values: Vec<_> = statistics
+                        .values
+                        .iter()
+                        .map(|(value, count)| format!("{} ({})", value, count))
+                        .collect();
+                    vec![
+                        tag.clone(),
+                        statistics.count.to_string(),
+                        statistics.first.map(format_value).unwrap_or_default(),
+                        statistics.last.map(format_value).unwrap_or_default(),
+                        directories.join(", "),
+                        values.join(", "),
+                    ]
+                })
+                .collect();
+
+            table::print(&headers, &rows);
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the old tags by the new one in every name, then rename everything in a single
+/// transaction, after showing what changes.
+fn rewrite_tags(
+    old: &[&str],
+    new: &str,
+    paths: &[Utf8PathBuf],
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let configurations = ConfigurationCache::load_all(paths)?;
+
+    // Nothing is overwritten, collisions are shown and stop everything.
+    let renamer = Renamer::default();
+    let mut table = PreviewTable::default();
+    let mut transaction = RenameTransaction::default();
+    let mut new_paths = HashSet::new();
+
+    for path in paths {
+        let Some(configuration) = configurations.get(path) else {
+            continue;
+        };
+        let new = resolve_tag(&configuration.tag_configuration, new)?;
+
+        let original = StructuredPath::parse_from(path, configuration);
+        let mut edited = original.clone();
+        let replaced = edited
+            .name
+            .replace_tags(old, &new)
+            .with_context(|| fl!("edit-unwritable-tag", tag = new.as_str()))?;
+        if !replaced {
+            continue;
+        }
+
+        match renamer.plan(&edited) {
+            Ok(RenameOutcome::Unchanged | RenameOutcome::Skipped) => {}
+            Ok(RenameOutcome::Renamed(renamed)) => {
+                let new_path = Utf8PathBuf::from(renamed.to_string());
+                if new_paths.insert(new_path.clone()) {
+                    table.push(&original, &renamed, PreviewStatus::Renamed);
+                    transaction.push(path.clone(), new_path);
+                } else {
+                    table.push(&original, &renamed, PreviewStatus::Collision);
+                }
+            }
+            Err(RenameError::Collision { .. }) => {
+                table.push(&original, &edited, PreviewStatus::Collision)
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    table.print()?;
+
+    let collisions = table.collisions();
+    if collisions > 0 {
+        bail!(fl!("preview-collisions", count = collisions));
+    }
+
+    if transaction.is_empty() {
+        println!("{}", fl!("tags-nothing-to-rename"));
+        return Ok(());
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    transaction.commit(&renamer, &Journal::open_default()?)?;
+    println!(
+        "{}",
+        fl!("tags-renamed", count = transaction.renames().len())
+    );
+
+    Ok(())
+}
+
+fn format_value(value: TimestampValue) -> String {
+    match value {
+        TimestampValue::Date(date) => date.format("%Y-%m-%d").to_string(),
+        TimestampValue::DateTime(date_time) => date_time.format("%Y-%m-%d %H:%M").to_string(),
+        TimestampValue::OffsetDateTime(date_time) => {
+            date_time.format("%Y-%m-%d %H:%M %:z").to_string()
+        }
+        TimestampValue::Time(time) => time.format("%H:%M").to_string(),
+        TimestampValue::DateRange(start, end) => {
+            format!("{} – {}", start.format("%Y-%m-%d"), end.format("%Y-%m-%d"))
+        }
+        TimestampValue::Week(monday) => monday.format("%G-W%V").to_string(),
+        TimestampValue::Month(first_day) => first_day.format("%Y-%m").to_string(),
+        TimestampValue::Quarter(first_day) => {
+            format!("{}-Q{}", first_day.year(), first_day.month0() / 3 + 1)
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::i18n::fl;
+use backend::{Journal, JournalEntry, JournalError, UndoSelection};
+
+/// Rename files back using the journal, the most recent rename first.
+pub fn run(last: Option<usize>, session: Option<String>) -> anyhow::Result<()> {
+    let journal = Journal::open_default()?;
+
+    let selection = match (last, session) {
+        (Some(count), _) => UndoSelection::Last(count),
+        (None, Some(session)) => UndoSelection::Session(session),
+        (None, None) => UndoSelection::LastSession,
+    };
+
+    let undone = match journal.undo(&selection) {
+        Ok(undone) => undone,
+        // What was undone before the failure is shown too.
+        Err(JournalError::PartialUndo { undone, source }) => {
+            print_undone(&undone);
+            return Err(source.into());
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    if undone.is_empty() {
+        println!("{}", fl!("undo-nothing"));
+    }
+
+    print_undone(&undone);
+
+    Ok(())
+}
+
+fn print_undone(undone: &[JournalEntry]) {
+    for entry in undone {
+        println!(
+            "{}",
+            fl!(
+                "edit-renamed",
+                from = entry.new_path.as_str(),
+                to = entry.original_path.as_str()
+            )
+        );
+    }
+}
+
+/// Show the sessions still in the journal, the most recent last.
+pub fn list() -> anyhow::Result<()> {
+    let journal = Journal::open_default()?;
+    let entries = journal.entries()?;
+
+    let mut sessions: Vec<(&str, usize, String)> = Vec::new();
+    for entry in &entries {
+        match sessions.last_mut() {
+            Some((session, count, _)) if *session == entry.session => *count += 1,
+            _ => sessions.push((&entry.session, 1, entry.time.format("%c").to_string())),
+        }
+    }
+
+    for (session, count, time) in sessions {
+        println!(
+            "{}",
+            fl!(
+                "undo-session",
+                session = session,
+                count = count,
+                time = time.as_str()
+            )
+        );
+    }
+
+    Ok(())
+}